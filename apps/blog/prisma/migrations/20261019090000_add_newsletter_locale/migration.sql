@@ -0,0 +1 @@
+ALTER TABLE "NewsletterSubscription" ADD COLUMN "locale" TEXT NOT NULL DEFAULT 'ko';
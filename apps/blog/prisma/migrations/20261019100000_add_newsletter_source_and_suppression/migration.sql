@@ -0,0 +1,12 @@
+ALTER TABLE "NewsletterSubscription" ADD COLUMN "source" TEXT NOT NULL DEFAULT 'web';
+
+CREATE TABLE "NewsletterSuppression" (
+    "id" TEXT NOT NULL,
+    "email" TEXT NOT NULL,
+    "reason" TEXT NOT NULL,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "NewsletterSuppression_pkey" PRIMARY KEY ("id")
+);
+
+CREATE UNIQUE INDEX "NewsletterSuppression_email_key" ON "NewsletterSuppression"("email");
@@ -0,0 +1,57 @@
+CREATE TABLE "Job" (
+    "id" TEXT NOT NULL,
+    "kind" TEXT NOT NULL,
+    "payload" JSONB NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'PENDING',
+    "runAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "attempts" INTEGER NOT NULL DEFAULT 0,
+    "maxAttempts" INTEGER NOT NULL DEFAULT 5,
+    "lastError" TEXT,
+    "lockedAt" TIMESTAMP(3),
+    "lockedBy" TEXT,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "Job_pkey" PRIMARY KEY ("id")
+);
+
+CREATE INDEX "Job_status_runAt_idx" ON "Job"("status", "runAt");
+
+CREATE TABLE "NewsletterIssue" (
+    "id" TEXT NOT NULL,
+    "subject" TEXT NOT NULL,
+    "html" TEXT NOT NULL,
+    "text" TEXT,
+    "locale" TEXT NOT NULL DEFAULT 'ko',
+    "status" TEXT NOT NULL DEFAULT 'DRAFT',
+    "scheduledAt" TIMESTAMP(3),
+    "sentAt" TIMESTAMP(3),
+    "jobId" TEXT,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updatedAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "NewsletterIssue_pkey" PRIMARY KEY ("id")
+);
+
+CREATE INDEX "NewsletterIssue_status_idx" ON "NewsletterIssue"("status");
+
+CREATE TABLE "NewsletterDelivery" (
+    "id" TEXT NOT NULL,
+    "issueId" TEXT NOT NULL,
+    "subscriptionId" TEXT NOT NULL,
+    "tokenHash" TEXT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'PENDING',
+    "error" TEXT,
+    "sentAt" TIMESTAMP(3),
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "NewsletterDelivery_pkey" PRIMARY KEY ("id")
+);
+
+CREATE UNIQUE INDEX "NewsletterDelivery_tokenHash_key" ON "NewsletterDelivery"("tokenHash");
+
+CREATE UNIQUE INDEX "NewsletterDelivery_issueId_subscriptionId_key" ON "NewsletterDelivery"("issueId", "subscriptionId");
+
+ALTER TABLE "NewsletterDelivery" ADD CONSTRAINT "NewsletterDelivery_issueId_fkey" FOREIGN KEY ("issueId") REFERENCES "NewsletterIssue"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+ALTER TABLE "NewsletterDelivery" ADD CONSTRAINT "NewsletterDelivery_subscriptionId_fkey" FOREIGN KEY ("subscriptionId") REFERENCES "NewsletterSubscription"("id") ON DELETE CASCADE ON UPDATE CASCADE;
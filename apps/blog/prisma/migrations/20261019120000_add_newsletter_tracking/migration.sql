@@ -0,0 +1,20 @@
+ALTER TABLE "NewsletterSubscription" ADD COLUMN "trackingOptOut" BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE "NewsletterIssue" ADD COLUMN "trackingEnabled" BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE "NewsletterDelivery" ADD COLUMN "tracked" BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE "NewsletterEvent" (
+    "id" TEXT NOT NULL,
+    "deliveryId" TEXT NOT NULL,
+    "issueId" TEXT NOT NULL,
+    "type" TEXT NOT NULL,
+    "url" TEXT,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "NewsletterEvent_pkey" PRIMARY KEY ("id")
+);
+
+CREATE INDEX "NewsletterEvent_issueId_type_idx" ON "NewsletterEvent"("issueId", "type");
+
+ALTER TABLE "NewsletterEvent" ADD CONSTRAINT "NewsletterEvent_deliveryId_fkey" FOREIGN KEY ("deliveryId") REFERENCES "NewsletterDelivery"("id") ON DELETE CASCADE ON UPDATE CASCADE;
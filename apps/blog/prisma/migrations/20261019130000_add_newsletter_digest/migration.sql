@@ -0,0 +1,15 @@
+ALTER TABLE "NewsletterIssue" ADD COLUMN "kind" TEXT NOT NULL DEFAULT 'manual';
+
+CREATE TABLE "NewsletterDigestPost" (
+    "id" TEXT NOT NULL,
+    "slug" TEXT NOT NULL,
+    "locale" TEXT NOT NULL,
+    "issueId" TEXT NOT NULL,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "NewsletterDigestPost_pkey" PRIMARY KEY ("id")
+);
+
+CREATE UNIQUE INDEX "NewsletterDigestPost_slug_locale_key" ON "NewsletterDigestPost"("slug", "locale");
+
+ALTER TABLE "NewsletterDigestPost" ADD CONSTRAINT "NewsletterDigestPost_issueId_fkey" FOREIGN KEY ("issueId") REFERENCES "NewsletterIssue"("id") ON DELETE CASCADE ON UPDATE CASCADE;
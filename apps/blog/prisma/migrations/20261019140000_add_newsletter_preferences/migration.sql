@@ -0,0 +1,5 @@
+ALTER TABLE "NewsletterSubscription" ADD COLUMN "topics" TEXT[] NOT NULL DEFAULT ARRAY['essays', 'dev_notes', 'shop_news']::TEXT[],
+ADD COLUMN "frequency" TEXT NOT NULL DEFAULT 'every_post',
+ADD COLUMN "pausedUntil" TIMESTAMP(3);
+
+ALTER TABLE "NewsletterIssue" ADD COLUMN "topic" TEXT;
@@ -0,0 +1,3 @@
+ALTER TABLE "NewsletterIssue" ADD COLUMN "membersOnly" BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX "NewsletterIssue_locale_sentAt_idx" ON "NewsletterIssue"("locale", "sentAt");
@@ -0,0 +1,3 @@
+ALTER TABLE "Message" ADD COLUMN "sources" JSONB;
+
+CREATE INDEX "Message_threadId_createdAt_idx" ON "Message"("threadId", "createdAt");
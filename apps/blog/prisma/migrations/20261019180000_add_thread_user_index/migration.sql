@@ -0,0 +1 @@
+CREATE INDEX "Thread_userId_updatedAt_idx" ON "Thread"("userId", "updatedAt");
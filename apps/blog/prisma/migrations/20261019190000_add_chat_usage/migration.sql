@@ -0,0 +1,11 @@
+CREATE TABLE "ChatUsage" (
+    "subject" TEXT NOT NULL,
+    "day" DATE NOT NULL,
+    "questions" INTEGER NOT NULL DEFAULT 0,
+    "inkSpent" INTEGER NOT NULL DEFAULT 0,
+    "updatedAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "ChatUsage_pkey" PRIMARY KEY ("subject", "day")
+);
+
+CREATE INDEX "ChatUsage_day_idx" ON "ChatUsage"("day");
@@ -0,0 +1,16 @@
+CREATE TABLE "ChatAnswerCache" (
+    "queryKey" TEXT NOT NULL,
+    "locale" TEXT NOT NULL,
+    "query" TEXT NOT NULL,
+    "answer" TEXT NOT NULL,
+    "sources" JSONB,
+    "done" JSONB,
+    "hits" INTEGER NOT NULL DEFAULT 0,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "expiresAt" TIMESTAMP(3) NOT NULL,
+    "lastHitAt" TIMESTAMP(3),
+
+    CONSTRAINT "ChatAnswerCache_pkey" PRIMARY KEY ("queryKey", "locale")
+);
+
+CREATE INDEX "ChatAnswerCache_expiresAt_idx" ON "ChatAnswerCache"("expiresAt");
@@ -0,0 +1,16 @@
+CREATE TABLE "ChatGuardHit" (
+    "id" TEXT NOT NULL,
+    "rule" TEXT NOT NULL,
+    "action" TEXT NOT NULL,
+    "detail" TEXT,
+    "userId" TEXT,
+    "locale" TEXT NOT NULL,
+    "query" TEXT NOT NULL,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "ChatGuardHit_pkey" PRIMARY KEY ("id")
+);
+
+CREATE INDEX "ChatGuardHit_rule_createdAt_idx" ON "ChatGuardHit"("rule", "createdAt");
+
+ALTER TABLE "ChatGuardHit" ADD CONSTRAINT "ChatGuardHit_userId_fkey" FOREIGN KEY ("userId") REFERENCES "User"("id") ON DELETE SET NULL ON UPDATE CASCADE;
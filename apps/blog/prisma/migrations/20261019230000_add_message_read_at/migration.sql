@@ -0,0 +1,3 @@
+ALTER TABLE "Message" ADD COLUMN "readAt" TIMESTAMP(3);
+
+CREATE INDEX "Message_threadId_readAt_idx" ON "Message"("threadId", "readAt");
@@ -0,0 +1 @@
+ALTER TABLE "User" ADD COLUMN "dmEmailNotifications" BOOLEAN NOT NULL DEFAULT true;
@@ -0,0 +1 @@
+ALTER TABLE "Message" ADD COLUMN "incomplete" BOOLEAN NOT NULL DEFAULT false;
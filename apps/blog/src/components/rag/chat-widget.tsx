@@ -56,6 +56,9 @@ export function ChatWidget() {
           });
         } else if (chunk.type === 'done') {
           setIsLoading(false);
+        } else if (chunk.type === 'error') {
+          // Terminal event: the stream timed out or was interrupted
+          throw new Error(chunk.message || chunk.code || 'Chat stream error');
         }
       }
     } catch (error) {
@@ -30,7 +30,7 @@ export interface ChatResponse {
 export async function* streamChat(
   query: string,
   locale: string = 'ko'
-): AsyncGenerator<{ type: 'sources' | 'content' | 'done'; sources?: SourceDocument[]; content?: string }> {
+): AsyncGenerator<{ type: 'sources' | 'content' | 'done' | 'error'; sources?: SourceDocument[]; content?: string; code?: string; message?: string }> {
   console.log('🚀 Starting chat stream:', { query, locale, url: blogApiUrl("/api/chat") });
 
   const response = await fetch(blogApiUrl("/api/chat"), {
@@ -54,10 +54,24 @@ async fn root() -> Json<ApiInfo> {
             "POST /api/newsletter/subscribe-direct".to_string(),
             "POST /api/newsletter/confirm".to_string(),
             "POST /api/newsletter/unsubscribe".to_string(),
+            "POST /api/newsletter/tracking".to_string(),
+            "POST /api/newsletter/preferences".to_string(),
+            "POST /api/newsletter/preferences/update".to_string(),
+            "GET /api/newsletter/archive".to_string(),
+            "GET /api/newsletter/archive/:id".to_string(),
+            "GET /api/newsletter/t/open".to_string(),
+            "GET /api/newsletter/t/click".to_string(),
             "POST /api/checkout/create-session".to_string(),
             "POST /api/webhook/stripe".to_string(),
             "POST /api/chat".to_string(),
             "POST /api/chat/simple".to_string(),
+            "POST /api/chat/quota".to_string(),
+            "POST /api/chat/threads/list".to_string(),
+            "POST /api/chat/threads/messages".to_string(),
+            "POST /api/chat/threads/rename".to_string(),
+            "POST /api/chat/threads/delete".to_string(),
+            "POST /api/chat/feedback".to_string(),
+            "GET /api/chat/ws".to_string(),
             "GET  /api/search".to_string(),
             "GET  /api/shop/products".to_string(),
             "POST /api/shop/ink-points".to_string(),
@@ -67,7 +81,33 @@ async fn root() -> Json<ApiInfo> {
             "POST /api/admin/dashboard".to_string(),
             "POST /api/admin/users".to_string(),
             "POST /api/admin/users/ink-points".to_string(),
+            "POST /api/admin/newsletter/export".to_string(),
+            "POST /api/admin/newsletter/import".to_string(),
+            "POST /api/admin/newsletter/analytics".to_string(),
+            "POST /api/admin/newsletter/issues".to_string(),
+            "POST /api/admin/newsletter/issues/list".to_string(),
+            "POST /api/admin/newsletter/issues/schedule".to_string(),
+            "POST /api/admin/newsletter/issues/reschedule".to_string(),
+            "POST /api/admin/newsletter/issues/cancel".to_string(),
+            "POST /api/admin/newsletter/issues/stats".to_string(),
+            "POST /api/admin/newsletter/digest".to_string(),
+            "POST /api/admin/email-templates/list".to_string(),
+            "POST /api/admin/email-templates/preview".to_string(),
+            "POST /api/admin/email-outbox/list".to_string(),
+            "POST /api/admin/email-outbox/retry".to_string(),
+            "POST /api/admin/chat/metrics".to_string(),
+            "POST /api/admin/chat/feedback/worst".to_string(),
+            "POST /api/admin/chat/cache/list".to_string(),
+            "POST /api/admin/chat/cache/purge".to_string(),
+            "POST /api/admin/chat/guard/hits".to_string(),
+            "POST /api/admin/dm/threads".to_string(),
+            "POST /api/admin/dm/messages".to_string(),
+            "POST /api/admin/dm/reply".to_string(),
+            "POST /api/admin/dm/mark".to_string(),
             "POST /api/admin-dm".to_string(),
+            "POST /api/admin-dm/messages".to_string(),
+            "POST /api/admin-dm/unread".to_string(),
+            "POST /api/admin-dm/notifications".to_string(),
             "POST /api/onboarding/complete".to_string(),
         ],
     })
@@ -110,6 +150,10 @@ async fn main() {
     let state = Arc::new(AppState::new());
     tracing::info!("Database connection pool initialized");
 
+    services::jobs::spawn_worker(state.clone());
+    services::outbox::spawn_worker(state.clone());
+    services::digest::ensure_scheduled(state.clone());
+
     let router = Router::<Arc<AppState>>::new()
         .route("/", get(root))
         .route("/health", get(health))
@@ -139,5 +183,7 @@ async fn main() {
     tracing::info!("🚀 Blog API listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
 }
@@ -52,6 +52,7 @@ pub struct User {
     pub terms_accepted_at: Option<NaiveDateTime>,
     pub onboarding_completed_at: Option<NaiveDateTime>,
     pub newsletter_opt_in_at: Option<NaiveDateTime>,
+    pub dm_email_notifications: bool,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -90,6 +91,12 @@ pub struct NewsletterSubscription {
     pub unsubscribed_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub locale: String,
+    pub source: String,
+    pub tracking_opt_out: bool,
+    pub topics: Vec<String>,
+    pub frequency: String,
+    pub paused_until: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Insertable)]
@@ -101,6 +108,8 @@ pub struct NewNewsletterSubscription {
     pub user_id: Option<String>,
     pub confirm_token_hash: Option<String>,
     pub unsubscribe_token_hash: Option<String>,
+    pub locale: String,
+    pub source: String,
 }
 
 #[derive(Debug, AsChangeset)]
@@ -142,6 +151,9 @@ pub struct Message {
     pub role: String,
     pub content: String,
     pub created_at: NaiveDateTime,
+    pub sources: Option<serde_json::Value>,
+    pub read_at: Option<NaiveDateTime>,
+    pub incomplete: bool,
 }
 
 #[derive(Debug, Insertable)]
@@ -151,6 +163,8 @@ pub struct NewMessage {
     pub thread_id: String,
     pub role: String,
     pub content: String,
+    pub sources: Option<serde_json::Value>,
+    pub incomplete: bool,
 }
 
 #[derive(Debug, Queryable, Selectable, Serialize)]
@@ -209,3 +223,155 @@ pub struct NewMarginalia {
     pub tags: Vec<String>,
     pub user_id: String,
 }
+
+#[derive(Debug, Clone, Queryable, Selectable, Serialize)]
+#[diesel(table_name = jobs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Job {
+    pub id: String,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub run_at: NaiveDateTime,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub last_error: Option<String>,
+    pub locked_at: Option<NaiveDateTime>,
+    pub locked_by: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = jobs)]
+pub struct NewJob {
+    pub id: String,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub run_at: NaiveDateTime,
+    pub max_attempts: i32,
+}
+
+#[derive(Debug, Queryable, Selectable, Serialize)]
+#[diesel(table_name = email_outbox)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OutboxEmail {
+    pub id: String,
+    pub kind: String,
+    pub sender: String,
+    pub recipient: String,
+    pub subject: String,
+    pub html: String,
+    pub text: Option<String>,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+    pub last_error: Option<String>,
+    pub locked_at: Option<NaiveDateTime>,
+    pub sent_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = email_outbox)]
+pub struct NewOutboxEmail {
+    pub id: String,
+    pub kind: String,
+    pub sender: String,
+    pub recipient: String,
+    pub subject: String,
+    pub html: String,
+    pub text: Option<String>,
+    pub max_attempts: i32,
+}
+
+#[derive(Debug, Queryable, Selectable, Serialize)]
+#[diesel(table_name = newsletter_issues)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewsletterIssue {
+    pub id: String,
+    pub subject: String,
+    pub html: String,
+    pub text: Option<String>,
+    pub locale: String,
+    pub status: String,
+    pub scheduled_at: Option<NaiveDateTime>,
+    pub sent_at: Option<NaiveDateTime>,
+    pub job_id: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub tracking_enabled: bool,
+    pub kind: String,
+    pub topic: Option<String>,
+    pub members_only: bool,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = newsletter_issues)]
+pub struct NewNewsletterIssue {
+    pub id: String,
+    pub subject: String,
+    pub html: String,
+    pub text: Option<String>,
+    pub locale: String,
+    pub tracking_enabled: bool,
+    pub kind: String,
+    pub topic: Option<String>,
+    pub members_only: bool,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = message_feedback)]
+pub struct NewMessageFeedback {
+    pub id: String,
+    pub message_id: String,
+    pub user_id: String,
+    pub rating: i32,
+    pub reason: Option<String>,
+    pub comment: Option<String>,
+    pub query: String,
+    pub locale: String,
+    pub source_slugs: Vec<String>,
+}
+
+#[derive(Debug, Queryable, Selectable, Serialize)]
+#[diesel(table_name = chat_answer_cache)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CachedAnswer {
+    pub query_key: String,
+    pub locale: String,
+    pub query: String,
+    pub answer: String,
+    pub sources: Option<serde_json::Value>,
+    pub done: Option<serde_json::Value>,
+    pub hits: i32,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub last_hit_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = chat_answer_cache)]
+pub struct NewCachedAnswer {
+    pub query_key: String,
+    pub locale: String,
+    pub query: String,
+    pub answer: String,
+    pub sources: Option<serde_json::Value>,
+    pub done: Option<serde_json::Value>,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = chat_guard_hits)]
+pub struct NewChatGuardHit {
+    pub id: String,
+    pub rule: String,
+    pub action: String,
+    pub detail: Option<String>,
+    pub user_id: Option<String>,
+    pub locale: String,
+    pub query: String,
+}
@@ -465,4 +465,9 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/dashboard", post(dashboard))
         .route("/users", post(list_users))
         .route("/users/ink-points", post(update_user_ink_points))
+        .nest("/newsletter", super::admin_newsletter::router())
+        .nest("/email-templates", super::admin_email::router())
+        .nest("/email-outbox", super::admin_outbox::router())
+        .nest("/chat", super::admin_chat::router())
+        .nest("/dm", super::admin_inbox::router())
 }
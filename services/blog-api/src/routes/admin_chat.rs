@@ -0,0 +1,503 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json,
+    Router,
+};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{Array, BigInt, Integer, Nullable, Text, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::auth::verify_internal_api_key;
+use crate::schema::{chat_answer_cache, chat_guard_hits};
+use crate::services::answer_cache;
+use crate::services::chat_stream::ChatMetricsSnapshot;
+use crate::services::AppState;
+
+#[derive(Deserialize)]
+pub struct ChatMetricsRequest {
+    pub user_role: String,
+}
+
+#[derive(Deserialize)]
+pub struct WorstRatedRequest {
+    pub user_role: String,
+    /// Only ratings from the last `days` days (default 30).
+    pub days: Option<i32>,
+    pub locale: Option<String>,
+    /// Questions need at least this many ratings to be listed (default 1).
+    pub min_ratings: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(QueryableByName)]
+struct WorstRatedRow {
+    #[diesel(sql_type = Text)]
+    query: String,
+    #[diesel(sql_type = Text)]
+    locale: String,
+    #[diesel(sql_type = BigInt)]
+    down_votes: i64,
+    #[diesel(sql_type = BigInt)]
+    up_votes: i64,
+    #[diesel(sql_type = Array<Text>)]
+    reasons: Vec<String>,
+    #[diesel(sql_type = Array<Text>)]
+    comments: Vec<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    source_slugs: Option<String>,
+    #[diesel(sql_type = Timestamp)]
+    last_rated_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct WorstRatedQuestion {
+    pub query: String,
+    pub locale: String,
+    pub down_votes: i64,
+    pub up_votes: i64,
+    pub down_ratio: f64,
+    pub reasons: Vec<String>,
+    /// Up to three comments on "down" ratings, newest first.
+    pub recent_comments: Vec<String>,
+    /// Every source cited by the rated answers.
+    pub source_slugs: Vec<String>,
+    pub last_rated_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct WorstRatedResponse {
+    pub questions: Vec<WorstRatedQuestion>,
+}
+
+#[derive(Deserialize)]
+pub struct ListCacheRequest {
+    pub user_role: String,
+    pub locale: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct PurgeCacheRequest {
+    pub user_role: String,
+    /// Purges this question; matched the way the cache matches questions.
+    pub query: Option<String>,
+    pub locale: Option<String>,
+    /// Required to purge everything when neither `query` nor `locale` is set.
+    #[serde(default)]
+    pub all: bool,
+    /// Only drops entries that have already expired.
+    #[serde(default)]
+    pub expired_only: bool,
+}
+
+#[derive(Serialize, Queryable)]
+pub struct CachedAnswerSummary {
+    pub query: String,
+    pub locale: String,
+    pub answer_chars: i32,
+    pub hits: i32,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub last_hit_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct ListCacheResponse {
+    pub total: i64,
+    pub entries: Vec<CachedAnswerSummary>,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[derive(Serialize)]
+pub struct PurgeCacheResponse {
+    pub success: bool,
+    pub message: String,
+    pub removed: usize,
+}
+
+impl PurgeCacheResponse {
+    fn error(message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+            removed: 0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct GuardHitsRequest {
+    pub user_role: String,
+    /// Only hits from the last `days` days (default 7).
+    pub days: Option<i64>,
+    pub rule: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct GuardRuleCount {
+    pub rule: String,
+    pub action: String,
+    pub hits: i64,
+}
+
+#[derive(Serialize, Queryable)]
+pub struct GuardHitSummary {
+    pub id: String,
+    pub rule: String,
+    pub action: String,
+    pub detail: Option<String>,
+    pub user_id: Option<String>,
+    pub locale: String,
+    pub query: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct GuardHitsResponse {
+    pub counts: Vec<GuardRuleCount>,
+    pub hits: Vec<GuardHitSummary>,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+/// POST /api/admin/chat/metrics
+/// Chat request outcomes on this instance since it started.
+async fn chat_metrics(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ChatMetricsRequest>,
+) -> (StatusCode, Json<Option<ChatMetricsSnapshot>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(None));
+    }
+
+    (StatusCode::OK, Json(Some(state.chat_metrics.snapshot())))
+}
+
+/// POST /api/admin/chat/feedback/worst
+/// Questions with the most "not helpful" ratings. Ratings are grouped by
+/// question text (case and surrounding whitespace ignored) and locale.
+async fn worst_rated(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<WorstRatedRequest>,
+) -> (StatusCode, Json<Option<WorstRatedResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(None));
+    }
+
+    let days = payload.days.unwrap_or(30).clamp(1, 365);
+    let min_ratings = payload.min_ratings.unwrap_or(1).max(1);
+    let limit = payload.limit.unwrap_or(20).clamp(1, 100);
+    let locale = payload.locale;
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let sql = r#"
+            SELECT
+                (array_agg(f.query ORDER BY f."updatedAt" DESC))[1] AS query,
+                f.locale AS locale,
+                COUNT(*) FILTER (WHERE f.rating < 0)::bigint AS down_votes,
+                COUNT(*) FILTER (WHERE f.rating > 0)::bigint AS up_votes,
+                array_remove(array_agg(DISTINCT f.reason), NULL) AS reasons,
+                (array_remove(array_agg(f.comment ORDER BY f."updatedAt" DESC) FILTER (WHERE f.rating < 0), NULL))[1:3] AS comments,
+                string_agg(array_to_string(f."sourceSlugs", ','), ',') AS source_slugs,
+                MAX(f."updatedAt") AS last_rated_at
+            FROM "MessageFeedback" f
+            WHERE f."updatedAt" >= NOW() - make_interval(days => $1)
+              AND ($2::text IS NULL OR f.locale = $2)
+            GROUP BY lower(btrim(f.query)), f.locale
+            HAVING COUNT(*) FILTER (WHERE f.rating < 0) > 0
+               AND COUNT(*) >= $3
+            ORDER BY
+                down_votes DESC,
+                COUNT(*) FILTER (WHERE f.rating < 0)::float8 / COUNT(*) DESC,
+                last_rated_at DESC
+            LIMIT $4
+        "#;
+
+        let rows: Vec<WorstRatedRow> = sql_query(sql)
+            .bind::<Integer, _>(days)
+            .bind::<Nullable<Text>, _>(locale)
+            .bind::<BigInt, _>(min_ratings)
+            .bind::<BigInt, _>(limit)
+            .load(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let questions = rows
+            .into_iter()
+            .map(|row| {
+                let mut source_slugs: Vec<String> = row
+                    .source_slugs
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|slug| !slug.is_empty())
+                    .map(|slug| slug.to_string())
+                    .collect();
+                source_slugs.sort();
+                source_slugs.dedup();
+
+                WorstRatedQuestion {
+                    down_ratio: row.down_votes as f64 / (row.down_votes + row.up_votes) as f64,
+                    query: row.query,
+                    locale: row.locale,
+                    down_votes: row.down_votes,
+                    up_votes: row.up_votes,
+                    reasons: row.reasons,
+                    recent_comments: row.comments,
+                    source_slugs,
+                    last_rated_at: row.last_rated_at,
+                }
+            })
+            .collect();
+
+        Ok::<_, String>(WorstRatedResponse { questions })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(response) => (StatusCode::OK, Json(Some(response))),
+        Err(e) => {
+            tracing::error!("worst_rated error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// POST /api/admin/chat/cache/list
+/// Cached answers, most used first. Expired entries are included until the
+/// next answer is cached or they are purged.
+async fn list_cache(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ListCacheRequest>,
+) -> (StatusCode, Json<Option<ListCacheResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(None));
+    }
+
+    let page = payload.page.unwrap_or(1).max(1);
+    let per_page = payload.per_page.unwrap_or(50).clamp(1, 200);
+    let locale = payload.locale;
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let filtered = || {
+            let mut query = chat_answer_cache::table.into_boxed();
+            if let Some(locale) = &locale {
+                query = query.filter(chat_answer_cache::locale.eq(locale.clone()));
+            }
+            query
+        };
+
+        let total: i64 = filtered()
+            .count()
+            .get_result(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let entries: Vec<CachedAnswerSummary> = filtered()
+            .order((chat_answer_cache::hits.desc(), chat_answer_cache::created_at.desc()))
+            .limit(per_page)
+            .offset((page - 1) * per_page)
+            .select((
+                chat_answer_cache::query,
+                chat_answer_cache::locale,
+                diesel::dsl::sql::<Integer>(r#"char_length("answer")"#),
+                chat_answer_cache::hits,
+                chat_answer_cache::created_at,
+                chat_answer_cache::expires_at,
+                chat_answer_cache::last_hit_at,
+            ))
+            .load(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        Ok::<_, String>(ListCacheResponse {
+            total,
+            entries,
+            page,
+            per_page,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(response) => (StatusCode::OK, Json(Some(response))),
+        Err(e) => {
+            tracing::error!("list_cache error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// POST /api/admin/chat/cache/purge
+/// Drops one question (`query`, optionally in one `locale`), a whole
+/// locale, every expired entry (`expired_only`), or everything (`all`).
+async fn purge_cache(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<PurgeCacheRequest>,
+) -> (StatusCode, Json<PurgeCacheResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(PurgeCacheResponse::error("Unauthorized")));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(PurgeCacheResponse::error("Forbidden")));
+    }
+
+    let query = payload.query.filter(|q| !q.trim().is_empty());
+    let locale = payload.locale.filter(|l| !l.trim().is_empty());
+    if !payload.expired_only && !payload.all && query.is_none() && locale.is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(PurgeCacheResponse::error("Specify query, locale, expired_only or all")),
+        );
+    }
+
+    let expired_only = payload.expired_only;
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        if expired_only {
+            answer_cache::purge_expired(&mut conn)
+        } else {
+            answer_cache::purge(&mut conn, query.as_deref(), locale.as_deref())
+        }
+        .map_err(|e| format!("DB delete error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(removed) => (
+            StatusCode::OK,
+            Json(PurgeCacheResponse {
+                success: true,
+                message: format!("Removed {} cached answers", removed),
+                removed,
+            }),
+        ),
+        Err(e) => {
+            tracing::error!("purge_cache error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(PurgeCacheResponse::error("Failed to purge cached answers")),
+            )
+        }
+    }
+}
+
+/// POST /api/admin/chat/guard/hits
+/// How often each screening rule fired, and the latest hits (questions as
+/// stored after redaction).
+async fn guard_hits(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<GuardHitsRequest>,
+) -> (StatusCode, Json<Option<GuardHitsResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(None));
+    }
+
+    let since = chrono::Utc::now().naive_utc() - chrono::Duration::days(payload.days.unwrap_or(7).clamp(1, 365));
+    let page = payload.page.unwrap_or(1).max(1);
+    let per_page = payload.per_page.unwrap_or(50).clamp(1, 200);
+    let rule = payload.rule;
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let counts: Vec<(String, String, i64)> = chat_guard_hits::table
+            .filter(chat_guard_hits::created_at.ge(since))
+            .group_by((chat_guard_hits::rule, chat_guard_hits::action))
+            .select((chat_guard_hits::rule, chat_guard_hits::action, diesel::dsl::count_star()))
+            .order(diesel::dsl::count_star().desc())
+            .load(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let mut recent = chat_guard_hits::table
+            .filter(chat_guard_hits::created_at.ge(since))
+            .into_boxed();
+        if let Some(rule) = &rule {
+            recent = recent.filter(chat_guard_hits::rule.eq(rule.clone()));
+        }
+        let hits: Vec<GuardHitSummary> = recent
+            .order(chat_guard_hits::created_at.desc())
+            .limit(per_page)
+            .offset((page - 1) * per_page)
+            .select((
+                chat_guard_hits::id,
+                chat_guard_hits::rule,
+                chat_guard_hits::action,
+                chat_guard_hits::detail,
+                chat_guard_hits::user_id,
+                chat_guard_hits::locale,
+                chat_guard_hits::query,
+                chat_guard_hits::created_at,
+            ))
+            .load(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        Ok::<_, String>(GuardHitsResponse {
+            counts: counts
+                .into_iter()
+                .map(|(rule, action, hits)| GuardRuleCount { rule, action, hits })
+                .collect(),
+            hits,
+            page,
+            per_page,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(response) => (StatusCode::OK, Json(Some(response))),
+        Err(e) => {
+            tracing::error!("guard_hits error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/metrics", post(chat_metrics))
+        .route("/feedback/worst", post(worst_rated))
+        .route("/cache/list", post(list_cache))
+        .route("/cache/purge", post(purge_cache))
+        .route("/guard/hits", post(guard_hits))
+}
@@ -5,17 +5,15 @@ use axum::{
     Json,
     Router,
 };
-use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::auth::verify_internal_api_key;
-use crate::models::{NewMessage, NewThread};
-use crate::schema::{messages, threads};
+use crate::models::Message;
+use crate::services::admin_dm::{self, Side};
+use crate::services::dm_notify;
 use crate::services::AppState;
 
-const ADMIN_DM_THREAD_TITLE: &str = "__ADMIN_DM__";
-
 #[derive(Deserialize)]
 pub struct SendAdminDmRequest {
     pub user_id: String,
@@ -29,6 +27,51 @@ pub struct SendAdminDmResponse {
     pub thread_id: Option<String>,
 }
 
+#[derive(Deserialize)]
+pub struct DmMessagesRequest {
+    pub user_id: String,
+    /// Page 1 is the most recent messages; each page is returned oldest first.
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct DmUnreadRequest {
+    pub user_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct DmNotificationsRequest {
+    pub user_id: String,
+    /// Omit to read the current setting.
+    pub enabled: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct DmMessagesResponse {
+    /// `None` until the reader has written.
+    pub thread_id: Option<String>,
+    /// `user` messages are the reader's, `admin` messages are replies.
+    pub messages: Vec<Message>,
+    /// Replies that were unread before this request marked them read.
+    pub unread: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub has_more: bool,
+}
+
+#[derive(Serialize)]
+pub struct DmUnreadResponse {
+    pub unread: i64,
+}
+
+#[derive(Serialize)]
+pub struct DmNotificationsResponse {
+    /// Whether admin replies are emailed to the reader.
+    pub enabled: bool,
+}
+
+/// POST /api/admin-dm
 async fn send_admin_dm(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
@@ -57,49 +100,12 @@ async fn send_admin_dm(
     }
 
     let pool = state.db.clone();
-    let user_id = payload.user_id;
-    let content = payload.content;
 
     let result = tokio::task::spawn_blocking(move || {
         let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
-
-        conn.transaction::<_, anyhow::Error, _>(|conn| {
-            let existing_thread_id: Option<String> = threads::table
-                .filter(threads::user_id.eq(Some(user_id.as_str())))
-                .filter(threads::title.eq(Some(ADMIN_DM_THREAD_TITLE)))
-                .select(threads::id)
-                .first(conn)
-                .optional()?;
-
-            let thread_id = if let Some(id) = existing_thread_id {
-                id
-            } else {
-                let id = cuid2::create_id();
-                let new_thread = NewThread {
-                    id: id.clone(),
-                    title: Some(ADMIN_DM_THREAD_TITLE.to_string()),
-                    user_id: Some(user_id.clone()),
-                };
-                diesel::insert_into(threads::table)
-                    .values(&new_thread)
-                    .execute(conn)?;
-                id
-            };
-
-            let new_message = NewMessage {
-                id: cuid2::create_id(),
-                thread_id: thread_id.clone(),
-                role: "user".to_string(),
-                content,
-            };
-
-            diesel::insert_into(messages::table)
-                .values(&new_message)
-                .execute(conn)?;
-
-            Ok::<_, anyhow::Error>(thread_id)
-        })
-        .map_err(|e| e.to_string())
+        admin_dm::send_from_reader(&mut conn, &payload.user_id, &payload.content)
+            .map(|posted| posted.thread_id)
+            .map_err(|e| e.to_string())
     })
     .await
     .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
@@ -127,6 +133,133 @@ async fn send_admin_dm(
     }
 }
 
+/// POST /api/admin-dm/messages
+/// The reader's conversation with the admin. Reading it marks the admin's
+/// replies as read.
+async fn dm_messages(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<DmMessagesRequest>,
+) -> (StatusCode, Json<Option<DmMessagesResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    let page = payload.page.unwrap_or(1).max(1);
+    let per_page = payload.per_page.unwrap_or(50).clamp(1, 100);
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let thread_id = admin_dm::thread_of(&mut conn, &payload.user_id)
+            .map_err(|e| format!("DB query error: {}", e))?;
+        let Some(thread_id) = thread_id else {
+            return Ok(DmMessagesResponse {
+                thread_id: None,
+                messages: Vec::new(),
+                unread: 0,
+                page,
+                per_page,
+                has_more: false,
+            });
+        };
+
+        let (messages, has_more) = admin_dm::page(&mut conn, &thread_id, page, per_page)
+            .map_err(|e| format!("DB query error: {}", e))?;
+        let unread = admin_dm::mark_read(&mut conn, &thread_id, Side::Reader)
+            .map_err(|e| format!("DB update error: {}", e))?;
+
+        Ok::<_, String>(DmMessagesResponse {
+            thread_id: Some(thread_id),
+            messages,
+            unread: unread as i64,
+            page,
+            per_page,
+            has_more,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(response) => (StatusCode::OK, Json(Some(response))),
+        Err(e) => {
+            tracing::error!("dm_messages error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// POST /api/admin-dm/unread
+/// Admin replies the reader hasn't seen, for a badge.
+async fn dm_unread(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<DmUnreadRequest>,
+) -> (StatusCode, Json<Option<DmUnreadResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        let unread = match admin_dm::thread_of(&mut conn, &payload.user_id) {
+            Ok(Some(thread_id)) => admin_dm::unread_count(&mut conn, &thread_id, Side::Reader),
+            Ok(None) => Ok(0),
+            Err(e) => Err(e),
+        };
+        unread.map_err(|e| format!("DB query error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(unread) => (StatusCode::OK, Json(Some(DmUnreadResponse { unread }))),
+        Err(e) => {
+            tracing::error!("dm_unread error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// POST /api/admin-dm/notifications
+/// Reads or changes whether the reader is emailed about admin replies.
+async fn dm_notifications(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<DmNotificationsRequest>,
+) -> (StatusCode, Json<Option<DmNotificationsResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        dm_notify::reader_setting(&mut conn, &payload.user_id, payload.enabled)
+            .map_err(|e| format!("DB query error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(Some(enabled)) => (StatusCode::OK, Json(Some(DmNotificationsResponse { enabled }))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(e) => {
+            tracing::error!("dm_notifications error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
 pub fn router() -> Router<Arc<AppState>> {
-    Router::new().route("/", post(send_admin_dm))
+    Router::new()
+        .route("/", post(send_admin_dm))
+        .route("/messages", post(dm_messages))
+        .route("/unread", post(dm_unread))
+        .route("/notifications", post(dm_notifications))
 }
@@ -0,0 +1,123 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::auth::verify_internal_api_key;
+use crate::routes::admin::AdminRequest;
+use crate::services::templates::{TemplateError, TEMPLATE_NAMES};
+use crate::services::AppState;
+
+#[derive(Serialize)]
+pub struct TemplateListResponse {
+    pub templates: Vec<&'static str>,
+    pub locales: Vec<&'static str>,
+}
+
+#[derive(Deserialize)]
+pub struct PreviewRequest {
+    pub user_role: String,
+    pub template: String,
+    pub locale: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PreviewResponse {
+    pub success: bool,
+    pub message: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+impl PreviewResponse {
+    fn error(message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+            subject: String::new(),
+            html: String::new(),
+            text: String::new(),
+        }
+    }
+}
+
+/// POST /api/admin/email-templates/list
+async fn list_templates(
+    headers: HeaderMap,
+    Json(payload): Json<AdminRequest>,
+) -> (StatusCode, Json<Option<TemplateListResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(None));
+    }
+
+    (
+        StatusCode::OK,
+        Json(Some(TemplateListResponse {
+            templates: TEMPLATE_NAMES.to_vec(),
+            locales: vec!["ko", "en"],
+        })),
+    )
+}
+
+/// POST /api/admin/email-templates/preview
+/// Renders a template with sample data so copy changes can be checked
+/// before anything is sent.
+async fn preview_template(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<PreviewRequest>,
+) -> (StatusCode, Json<PreviewResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(PreviewResponse::error("Unauthorized")));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(PreviewResponse::error("Unauthorized")));
+    }
+
+    let locale = match payload.locale.as_deref() {
+        None | Some("ko") => "ko",
+        Some("en") => "en",
+        Some(_) => return (StatusCode::BAD_REQUEST, Json(PreviewResponse::error("Invalid locale"))),
+    };
+
+    match state.templates.render_sample(&payload.template, locale) {
+        Ok(content) => (
+            StatusCode::OK,
+            Json(PreviewResponse {
+                success: true,
+                message: "OK".to_string(),
+                subject: content.subject,
+                html: content.html,
+                text: content.text,
+            }),
+        ),
+        Err(TemplateError::Unknown(name)) => (
+            StatusCode::BAD_REQUEST,
+            Json(PreviewResponse::error(&format!("Unknown template: {}", name))),
+        ),
+        Err(e) => {
+            tracing::error!("preview_template error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(PreviewResponse::error(&e.to_string())),
+            )
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/list", post(list_templates))
+        .route("/preview", post(preview_template))
+}
@@ -0,0 +1,417 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json,
+    Router,
+};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Bool, Nullable, Text, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::auth::verify_internal_api_key;
+use crate::models::Message;
+use crate::services::admin_dm::{self, DmError, Side, MAX_REPLY_CHARS};
+use crate::services::threads::ADMIN_DM_THREAD_TITLE;
+use crate::services::AppState;
+
+const PREVIEW_CHARS: usize = 120;
+
+#[derive(Deserialize)]
+pub struct InboxRequest {
+    pub user_role: String,
+    #[serde(default)]
+    pub unread_only: bool,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct InboxThreadRequest {
+    pub user_role: String,
+    pub thread_id: String,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct InboxReplyRequest {
+    pub user_role: String,
+    pub thread_id: String,
+    pub content: String,
+}
+
+#[derive(Deserialize)]
+pub struct InboxMarkRequest {
+    pub user_role: String,
+    pub thread_id: String,
+    /// `true` marks the reader's latest message unread again.
+    #[serde(default)]
+    pub unread: bool,
+}
+
+#[derive(QueryableByName)]
+struct InboxRow {
+    #[diesel(sql_type = Text)]
+    thread_id: String,
+    #[diesel(sql_type = Text)]
+    user_id: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    user_name: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    user_email: Option<String>,
+    #[diesel(sql_type = BigInt)]
+    unread: i64,
+    #[diesel(sql_type = BigInt)]
+    message_count: i64,
+    #[diesel(sql_type = Nullable<Text>)]
+    last_role: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    last_content: Option<String>,
+    #[diesel(sql_type = Nullable<Timestamp>)]
+    last_at: Option<NaiveDateTime>,
+    #[diesel(sql_type = BigInt)]
+    total: i64,
+}
+
+#[derive(Serialize)]
+pub struct InboxThread {
+    pub thread_id: String,
+    pub user_id: String,
+    pub user_name: Option<String>,
+    pub user_email: Option<String>,
+    /// Reader messages the admin hasn't read.
+    pub unread: i64,
+    pub message_count: i64,
+    /// `user` or `admin`.
+    pub last_role: Option<String>,
+    pub last_preview: Option<String>,
+    pub last_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct InboxResponse {
+    pub threads: Vec<InboxThread>,
+    /// Threads matching the request, across all pages.
+    pub total: i64,
+    /// Unread reader messages across every thread.
+    pub total_unread: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[derive(Serialize)]
+pub struct InboxThreadResponse {
+    pub thread_id: String,
+    pub user_id: String,
+    pub messages: Vec<Message>,
+    /// Reader messages that were unread before this request marked them read.
+    pub unread: i64,
+    pub page: i64,
+    pub per_page: i64,
+    pub has_more: bool,
+}
+
+#[derive(Serialize)]
+pub struct InboxActionResponse {
+    pub success: bool,
+    pub message: String,
+    pub message_id: Option<String>,
+}
+
+impl InboxActionResponse {
+    fn error(message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+            message_id: None,
+        }
+    }
+}
+
+fn preview(content: &str) -> String {
+    let text = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= PREVIEW_CHARS {
+        return text;
+    }
+    let mut cut: String = text.chars().take(PREVIEW_CHARS - 1).collect();
+    cut.push('…');
+    cut
+}
+
+fn authorize(headers: &HeaderMap, user_role: &str) -> Result<(), StatusCode> {
+    if verify_internal_api_key(headers).is_err() {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    if user_role != "ADMIN" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(())
+}
+
+/// POST /api/admin/dm/threads
+/// DM threads, most recent activity first, with unread counts.
+async fn list_inbox(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<InboxRequest>,
+) -> (StatusCode, Json<Option<InboxResponse>>) {
+    if let Err(status) = authorize(&headers, &payload.user_role) {
+        return (status, Json(None));
+    }
+
+    let page = payload.page.unwrap_or(1).max(1);
+    let per_page = payload.per_page.unwrap_or(30).clamp(1, 100);
+    let unread_only = payload.unread_only;
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let sql = r#"
+            WITH inbox AS (
+                SELECT
+                    t.id AS thread_id,
+                    t."userId" AS user_id,
+                    u.name AS user_name,
+                    u.email AS user_email,
+                    t."updatedAt" AS updated_at,
+                    (SELECT COUNT(*) FROM "Message" c
+                      WHERE c."threadId" = t.id AND c.role = 'user' AND c."readAt" IS NULL)::bigint AS unread,
+                    (SELECT COUNT(*) FROM "Message" c WHERE c."threadId" = t.id)::bigint AS message_count
+                FROM "Thread" t
+                JOIN "User" u ON u.id = t."userId"
+                WHERE t.title = $1
+            )
+            SELECT
+                i.thread_id, i.user_id, i.user_name, i.user_email, i.unread, i.message_count,
+                m.role AS last_role,
+                m.content AS last_content,
+                m."createdAt" AS last_at,
+                COUNT(*) OVER ()::bigint AS total
+            FROM inbox i
+            LEFT JOIN LATERAL (
+                SELECT role, content, "createdAt"
+                FROM "Message"
+                WHERE "threadId" = i.thread_id
+                ORDER BY "createdAt" DESC
+                LIMIT 1
+            ) m ON true
+            WHERE NOT $2 OR i.unread > 0
+            ORDER BY i.updated_at DESC
+            LIMIT $3 OFFSET $4
+        "#;
+
+        let rows: Vec<InboxRow> = sql_query(sql)
+            .bind::<Text, _>(ADMIN_DM_THREAD_TITLE)
+            .bind::<Bool, _>(unread_only)
+            .bind::<BigInt, _>(per_page)
+            .bind::<BigInt, _>((page - 1) * per_page)
+            .load(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        #[derive(QueryableByName)]
+        struct Unread {
+            #[diesel(sql_type = BigInt)]
+            count: i64,
+        }
+
+        let total_unread = sql_query(
+            r#"
+            SELECT COUNT(*)::bigint AS count
+            FROM "Message" m
+            JOIN "Thread" t ON t.id = m."threadId"
+            WHERE t.title = $1 AND m.role = 'user' AND m."readAt" IS NULL
+            "#,
+        )
+        .bind::<Text, _>(ADMIN_DM_THREAD_TITLE)
+        .get_result::<Unread>(&mut conn)
+        .map_err(|e| format!("DB query error: {}", e))?
+        .count;
+
+        let total = rows.first().map(|row| row.total).unwrap_or(0);
+        let threads = rows
+            .into_iter()
+            .map(|row| InboxThread {
+                thread_id: row.thread_id,
+                user_id: row.user_id,
+                user_name: row.user_name,
+                user_email: row.user_email,
+                unread: row.unread,
+                message_count: row.message_count,
+                last_role: row.last_role,
+                last_preview: row.last_content.as_deref().map(preview),
+                last_at: row.last_at,
+            })
+            .collect();
+
+        Ok::<_, String>(InboxResponse {
+            threads,
+            total,
+            total_unread,
+            page,
+            per_page,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(response) => (StatusCode::OK, Json(Some(response))),
+        Err(e) => {
+            tracing::error!("list_inbox error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// POST /api/admin/dm/messages
+/// One DM thread. Reading it marks the reader's messages as read.
+async fn read_thread(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<InboxThreadRequest>,
+) -> (StatusCode, Json<Option<InboxThreadResponse>>) {
+    if let Err(status) = authorize(&headers, &payload.user_role) {
+        return (status, Json(None));
+    }
+
+    let page = payload.page.unwrap_or(1).max(1);
+    let per_page = payload.per_page.unwrap_or(50).clamp(1, 100);
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| DmError::Connection(e.to_string()))?;
+        let user_id = admin_dm::owner_of(&mut conn, &payload.thread_id)?;
+        let (messages, has_more) = admin_dm::page(&mut conn, &payload.thread_id, page, per_page)?;
+        let unread = admin_dm::mark_read(&mut conn, &payload.thread_id, Side::Admin)?;
+
+        Ok::<_, DmError>(InboxThreadResponse {
+            thread_id: payload.thread_id,
+            user_id,
+            messages,
+            unread: unread as i64,
+            page,
+            per_page,
+            has_more,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(DmError::Connection(format!("Task error: {}", e))));
+
+    match result {
+        Ok(response) => (StatusCode::OK, Json(Some(response))),
+        Err(DmError::NotFound) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(e) => {
+            tracing::error!("read_thread error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// POST /api/admin/dm/reply
+async fn reply(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<InboxReplyRequest>,
+) -> (StatusCode, Json<InboxActionResponse>) {
+    if let Err(status) = authorize(&headers, &payload.user_role) {
+        let message = if status == StatusCode::UNAUTHORIZED { "Unauthorized" } else { "Forbidden" };
+        return (status, Json(InboxActionResponse::error(message)));
+    }
+
+    let content = payload.content.trim().to_string();
+    if content.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(InboxActionResponse::error("Reply is empty")));
+    }
+    if content.chars().count() > MAX_REPLY_CHARS {
+        return (StatusCode::BAD_REQUEST, Json(InboxActionResponse::error("Reply is too long")));
+    }
+
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| DmError::Connection(e.to_string()))?;
+        admin_dm::reply_as_admin(&mut conn, &payload.thread_id, &content)
+    })
+    .await
+    .unwrap_or_else(|e| Err(DmError::Connection(format!("Task error: {}", e))));
+
+    match result {
+        Ok(posted) => (
+            StatusCode::OK,
+            Json(InboxActionResponse {
+                success: true,
+                message: "Reply sent".to_string(),
+                message_id: Some(posted.message_id),
+            }),
+        ),
+        Err(DmError::NotFound) => (StatusCode::NOT_FOUND, Json(InboxActionResponse::error("Thread not found"))),
+        Err(e) => {
+            tracing::error!("dm reply error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(InboxActionResponse::error("Failed to send reply")),
+            )
+        }
+    }
+}
+
+/// POST /api/admin/dm/mark
+/// Marks a thread read, or (`unread: true`) its latest reader message unread.
+async fn mark(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<InboxMarkRequest>,
+) -> (StatusCode, Json<InboxActionResponse>) {
+    if let Err(status) = authorize(&headers, &payload.user_role) {
+        let message = if status == StatusCode::UNAUTHORIZED { "Unauthorized" } else { "Forbidden" };
+        return (status, Json(InboxActionResponse::error(message)));
+    }
+
+    let pool = state.db.clone();
+    let unread = payload.unread;
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| DmError::Connection(e.to_string()))?;
+        admin_dm::owner_of(&mut conn, &payload.thread_id)?;
+        let changed = if unread {
+            admin_dm::mark_unread(&mut conn, &payload.thread_id, Side::Admin)?
+        } else {
+            admin_dm::mark_read(&mut conn, &payload.thread_id, Side::Admin)?
+        };
+        Ok::<_, DmError>(changed)
+    })
+    .await
+    .unwrap_or_else(|e| Err(DmError::Connection(format!("Task error: {}", e))));
+
+    match result {
+        Ok(changed) => (
+            StatusCode::OK,
+            Json(InboxActionResponse {
+                success: true,
+                message: format!("Updated {} messages", changed),
+                message_id: None,
+            }),
+        ),
+        Err(DmError::NotFound) => (StatusCode::NOT_FOUND, Json(InboxActionResponse::error("Thread not found"))),
+        Err(e) => {
+            tracing::error!("dm mark error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(InboxActionResponse::error("Failed to update thread")),
+            )
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/threads", post(list_inbox))
+        .route("/messages", post(read_thread))
+        .route("/reply", post(reply))
+        .route("/mark", post(mark))
+}
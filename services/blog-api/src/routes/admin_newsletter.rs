@@ -0,0 +1,1291 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json,
+    Router,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Double, Text, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::auth::verify_internal_api_key;
+use crate::models::{
+    NewNewsletterIssue, NewNewsletterSubscription, NewsletterIssue, NewsletterStatus,
+    NewsletterSubscription,
+};
+use crate::routes::admin::AdminRequest;
+use crate::routes::newsletter::{is_valid_email, normalize_email};
+use crate::schema::{newsletter_issues, newsletter_subscriptions, newsletter_suppressions};
+use crate::services::newsletter::{
+    generate_token, next_saturday_morning_kst, sha256_hash, SendIssuePayload, ISSUE_DRAFT, ISSUE_KIND_MANUAL, ISSUE_SCHEDULED,
+    ISSUE_SENDING, TOPICS,
+};
+use crate::services::digest::{self, DigestConfig};
+use crate::services::{jobs, AppState};
+
+/// Statuses from other mailing tools that mean "never mail this address again".
+const SUPPRESSED_IMPORT_STATUSES: &[&str] = &[
+    "unsubscribed",
+    "suppressed",
+    "bounced",
+    "complained",
+    "cleaned",
+];
+
+/// Statuses that mean the address confirmed its subscription in the other
+/// tool. Anything else (empty, `pending`, `unconfirmed`, ...) never went
+/// through double opt-in and is not imported.
+const CONFIRMED_IMPORT_STATUSES: &[&str] = &["active", "subscribed", "confirmed", "opted_in"];
+
+struct ImportRow {
+    email: Option<String>,
+    locale: String,
+    status: Option<String>,
+    confirmed_at: Option<NaiveDateTime>,
+}
+
+/// Confirmation times as other tools export them: RFC 3339, a plain
+/// `YYYY-MM-DD HH:MM:SS` (taken as UTC), or a bare date.
+fn parse_import_time(value: &str) -> Option<NaiveDateTime> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.naive_utc())
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+}
+
+#[derive(Deserialize)]
+pub struct ImportSubscribersRequest {
+    pub user_role: String,
+    pub csv: String,
+    pub source: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ImportSubscribersResponse {
+    pub success: bool,
+    pub message: String,
+    pub inserted: usize,
+    pub skipped: usize,
+    pub invalid: usize,
+    pub suppressed: usize,
+    /// Rows without a confirmed status, left out so they can't skip double opt-in.
+    pub unconfirmed: usize,
+}
+
+impl ImportSubscribersResponse {
+    fn error(message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+            inserted: 0,
+            skipped: 0,
+            invalid: 0,
+            suppressed: 0,
+            unconfirmed: 0,
+        }
+    }
+}
+
+fn format_timestamp(dt: Option<NaiveDateTime>) -> String {
+    dt.map(|dt| {
+        chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(dt, chrono::Utc).to_rfc3339()
+    })
+    .unwrap_or_default()
+}
+
+async fn export_subscribers(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<AdminRequest>,
+) -> Response {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, "Forbidden").into_response();
+    }
+
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let subscriptions: Vec<NewsletterSubscription> = newsletter_subscriptions::table
+            .order(newsletter_subscriptions::created_at.asc())
+            .load(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let suppressed: HashSet<String> = newsletter_suppressions::table
+            .select(newsletter_suppressions::email)
+            .load::<String>(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?
+            .into_iter()
+            .collect();
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer
+            .write_record([
+                "email",
+                "status",
+                "locale",
+                "source",
+                "suppressed",
+                "user_id",
+                "created_at",
+                "confirmed_at",
+                "unsubscribed_at",
+            ])
+            .map_err(|e| format!("CSV write error: {}", e))?;
+
+        for sub in subscriptions {
+            let is_suppressed = suppressed.contains(&sub.email);
+            writer
+                .write_record([
+                    sub.email,
+                    format!("{:?}", sub.status),
+                    sub.locale,
+                    sub.source,
+                    is_suppressed.to_string(),
+                    sub.user_id.unwrap_or_default(),
+                    format_timestamp(Some(sub.created_at)),
+                    format_timestamp(sub.confirmed_at),
+                    format_timestamp(sub.unsubscribed_at),
+                ])
+                .map_err(|e| format!("CSV write error: {}", e))?;
+        }
+
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| format!("CSV write error: {}", e))?;
+
+        String::from_utf8(bytes).map_err(|e| format!("CSV encoding error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(body) => {
+            let filename = format!(
+                "attachment; filename=\"newsletter-subscribers-{}.csv\"",
+                chrono::Utc::now().format("%Y%m%d")
+            );
+
+            (
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                    (header::CONTENT_DISPOSITION, filename),
+                ],
+                body,
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("export_subscribers error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error").into_response()
+        }
+    }
+}
+
+async fn import_subscribers(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ImportSubscribersRequest>,
+) -> (StatusCode, Json<ImportSubscribersResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ImportSubscribersResponse::error("Unauthorized")),
+        );
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ImportSubscribersResponse::error("Unauthorized")),
+        );
+    }
+
+    let source = payload
+        .source
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "import".to_string());
+
+    if source.len() > 64 {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ImportSubscribersResponse::error("Source is too long")),
+        );
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(payload.csv.as_bytes());
+
+    let header_row = match reader.headers() {
+        Ok(h) => h.clone(),
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ImportSubscribersResponse::error(&format!("Invalid CSV: {}", e))),
+            )
+        }
+    };
+
+    let column = |names: &[&str]| {
+        header_row
+            .iter()
+            .position(|h| names.iter().any(|n| h.eq_ignore_ascii_case(n)))
+    };
+
+    let Some(email_col) = column(&["email", "email_address", "e-mail"]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ImportSubscribersResponse::error("CSV must have an email column")),
+        );
+    };
+    // Only addresses the other tool marks as confirmed are imported, so a
+    // file without statuses would import nothing.
+    let Some(status_col) = column(&["status"]) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ImportSubscribersResponse::error(
+                "CSV must have a status column (only confirmed subscribers are imported)",
+            )),
+        );
+    };
+    let locale_col = column(&["locale", "language", "lang"]);
+    let confirmed_col = column(&["confirmed_at", "confirm_time", "optin_time", "opted_in_at"]);
+
+    let mut rows: Vec<ImportRow> = Vec::new();
+    for record in reader.records() {
+        match record {
+            Ok(record) => {
+                let field = |col: Option<usize>| {
+                    col.and_then(|c| record.get(c))
+                        .map(str::to_string)
+                        .filter(|v| !v.is_empty())
+                };
+                let email = field(Some(email_col))
+                    .map(|e| normalize_email(&e))
+                    .filter(|e| is_valid_email(e));
+                let locale = match field(locale_col).as_deref() {
+                    Some(l) if l.to_lowercase().starts_with("en") => "en",
+                    _ => "ko",
+                };
+                rows.push(ImportRow {
+                    email,
+                    locale: locale.to_string(),
+                    status: field(Some(status_col)),
+                    confirmed_at: field(confirmed_col).as_deref().and_then(parse_import_time),
+                });
+            }
+            Err(_) => rows.push(ImportRow {
+                email: None,
+                locale: String::new(),
+                status: None,
+                confirmed_at: None,
+            }),
+        }
+    }
+
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        let now = chrono::Utc::now().naive_utc();
+
+        conn.transaction::<_, anyhow::Error, _>(|conn| {
+            let existing: HashSet<String> = newsletter_subscriptions::table
+                .select(newsletter_subscriptions::email)
+                .load::<String>(conn)?
+                .into_iter()
+                .collect();
+
+            let mut suppressed: HashSet<String> = newsletter_suppressions::table
+                .select(newsletter_suppressions::email)
+                .load::<String>(conn)?
+                .into_iter()
+                .collect();
+
+            let mut seen: HashSet<String> = HashSet::new();
+            let mut report = ImportSubscribersResponse {
+                success: true,
+                message: String::new(),
+                inserted: 0,
+                skipped: 0,
+                invalid: 0,
+                suppressed: 0,
+                unconfirmed: 0,
+            };
+
+            for row in rows {
+                let Some(email) = row.email else {
+                    report.invalid += 1;
+                    continue;
+                };
+
+                if !seen.insert(email.clone()) {
+                    report.skipped += 1;
+                    continue;
+                }
+
+                let status = row.status.unwrap_or_default().to_lowercase();
+                if SUPPRESSED_IMPORT_STATUSES.contains(&status.as_str()) {
+                    if suppressed.insert(email.clone()) {
+                        diesel::insert_into(newsletter_suppressions::table)
+                            .values((
+                                newsletter_suppressions::id.eq(cuid2::create_id()),
+                                newsletter_suppressions::email.eq(&email),
+                                newsletter_suppressions::reason.eq(format!("{}:{}", source, status)),
+                            ))
+                            .on_conflict(newsletter_suppressions::email)
+                            .do_nothing()
+                            .execute(conn)?;
+                    }
+                    report.suppressed += 1;
+                    continue;
+                }
+
+                if !CONFIRMED_IMPORT_STATUSES.contains(&status.as_str()) {
+                    report.unconfirmed += 1;
+                    continue;
+                }
+
+                // Existing rows are left untouched, so an UNSUBSCRIBED address
+                // is never brought back to life by an import.
+                if existing.contains(&email) || suppressed.contains(&email) {
+                    report.skipped += 1;
+                    continue;
+                }
+
+                let new_sub = NewNewsletterSubscription {
+                    id: cuid2::create_id(),
+                    email,
+                    status: NewsletterStatus::ACTIVE,
+                    user_id: None,
+                    confirm_token_hash: None,
+                    // Like a confirmed sign-up, every active row carries its
+                    // own unsubscribe token.
+                    unsubscribe_token_hash: Some(sha256_hash(&generate_token())),
+                    locale: row.locale,
+                    source: source.clone(),
+                };
+
+                diesel::insert_into(newsletter_subscriptions::table)
+                    .values((
+                        &new_sub,
+                        newsletter_subscriptions::confirmed_at.eq(Some(row.confirmed_at.unwrap_or(now))),
+                        newsletter_subscriptions::updated_at.eq(now),
+                    ))
+                    .execute(conn)?;
+
+                report.inserted += 1;
+            }
+
+            report.message = format!(
+                "Imported {} subscribers ({} skipped, {} invalid, {} suppressed, {} unconfirmed)",
+                report.inserted, report.skipped, report.invalid, report.suppressed, report.unconfirmed
+            );
+
+            Ok(report)
+        })
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(report) => (StatusCode::OK, Json(report)),
+        Err(e) => {
+            tracing::error!("import_subscribers error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ImportSubscribersResponse::error("Failed to import subscribers")),
+            )
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AnalyticsRequest {
+    pub user_role: String,
+    /// Inclusive start date (`YYYY-MM-DD`, UTC). Defaults to 30 days ago.
+    pub from: Option<String>,
+    /// Inclusive end date (`YYYY-MM-DD`, UTC). Defaults to today.
+    pub to: Option<String>,
+    /// `day` or `week`. Defaults to `day`.
+    pub granularity: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AnalyticsSummary {
+    pub signups: i64,
+    pub confirmations: i64,
+    pub unsubscribes: i64,
+    pub net_growth: i64,
+    pub pending_never_confirmed: i64,
+    pub pending_never_confirmed_rate: f64,
+    pub active_total: i64,
+}
+
+#[derive(Serialize)]
+pub struct AnalyticsBucket {
+    pub period_start: NaiveDate,
+    pub signups: i64,
+    pub confirmations: i64,
+    pub unsubscribes: i64,
+    pub net_growth: i64,
+}
+
+#[derive(Serialize)]
+pub struct AnalyticsBreakdown {
+    pub key: String,
+    pub active: i64,
+    pub confirmations: i64,
+    pub unsubscribes: i64,
+}
+
+#[derive(Serialize)]
+pub struct NewsletterAnalytics {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub granularity: String,
+    pub summary: AnalyticsSummary,
+    pub series: Vec<AnalyticsBucket>,
+    pub by_locale: Vec<AnalyticsBreakdown>,
+    pub by_source: Vec<AnalyticsBreakdown>,
+}
+
+#[derive(QueryableByName)]
+struct AnalyticsSummaryRow {
+    #[diesel(sql_type = BigInt)]
+    signups: i64,
+    #[diesel(sql_type = BigInt)]
+    confirmations: i64,
+    #[diesel(sql_type = BigInt)]
+    unsubscribes: i64,
+    #[diesel(sql_type = BigInt)]
+    pending_never_confirmed: i64,
+    #[diesel(sql_type = Double)]
+    pending_never_confirmed_rate: f64,
+    #[diesel(sql_type = BigInt)]
+    active_total: i64,
+}
+
+#[derive(QueryableByName)]
+struct AnalyticsBucketRow {
+    #[diesel(sql_type = Timestamp)]
+    bucket: NaiveDateTime,
+    #[diesel(sql_type = BigInt)]
+    signups: i64,
+    #[diesel(sql_type = BigInt)]
+    confirmations: i64,
+    #[diesel(sql_type = BigInt)]
+    unsubscribes: i64,
+}
+
+#[derive(QueryableByName)]
+struct AnalyticsBreakdownRow {
+    #[diesel(sql_type = Text)]
+    key: String,
+    #[diesel(sql_type = BigInt)]
+    active: i64,
+    #[diesel(sql_type = BigInt)]
+    confirmations: i64,
+    #[diesel(sql_type = BigInt)]
+    unsubscribes: i64,
+}
+
+fn parse_date(value: Option<&str>, default: NaiveDate) -> Result<NaiveDate, String> {
+    match value.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => NaiveDate::parse_from_str(v, "%Y-%m-%d").map_err(|_| format!("Invalid date: {}", v)),
+        None => Ok(default),
+    }
+}
+
+fn breakdown_sql(column: &str) -> String {
+    format!(
+        r#"
+            SELECT
+                {column}::text AS key,
+                COUNT(*) FILTER (WHERE status = 'ACTIVE')::bigint AS active,
+                COUNT(*) FILTER (WHERE "confirmedAt" >= $1 AND "confirmedAt" < $2)::bigint AS confirmations,
+                COUNT(*) FILTER (WHERE "unsubscribedAt" >= $1 AND "unsubscribedAt" < $2)::bigint AS unsubscribes
+            FROM "NewsletterSubscription"
+            GROUP BY {column}
+            ORDER BY active DESC, key ASC
+        "#
+    )
+}
+
+async fn analytics(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<AnalyticsRequest>,
+) -> (StatusCode, Json<Option<NewsletterAnalytics>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(None));
+    }
+
+    let today = chrono::Utc::now().date_naive();
+    let range = parse_date(payload.to.as_deref(), today).and_then(|to| {
+        let from = parse_date(payload.from.as_deref(), to - chrono::Duration::days(29))?;
+        Ok((from, to))
+    });
+
+    let (from, to) = match range {
+        Ok((from, to)) if from <= to && (to - from).num_days() <= 366 => (from, to),
+        _ => return (StatusCode::BAD_REQUEST, Json(None)),
+    };
+
+    let granularity = match payload.granularity.as_deref() {
+        None | Some("day") => "day",
+        Some("week") => "week",
+        Some(_) => return (StatusCode::BAD_REQUEST, Json(None)),
+    };
+
+    let range_start = from.and_hms_opt(0, 0, 0).unwrap_or_default();
+    let range_end = (to + chrono::Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default();
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let summary_sql = r#"
+            SELECT
+                COUNT(*) FILTER (WHERE "createdAt" >= $1 AND "createdAt" < $2)::bigint AS signups,
+                COUNT(*) FILTER (WHERE "confirmedAt" >= $1 AND "confirmedAt" < $2)::bigint AS confirmations,
+                COUNT(*) FILTER (WHERE "unsubscribedAt" >= $1 AND "unsubscribedAt" < $2)::bigint AS unsubscribes,
+                COUNT(*) FILTER (
+                    WHERE "createdAt" >= $1 AND "createdAt" < $2 AND "confirmedAt" IS NULL
+                )::bigint AS pending_never_confirmed,
+                COALESCE(
+                    COUNT(*) FILTER (
+                        WHERE "createdAt" >= $1 AND "createdAt" < $2 AND "confirmedAt" IS NULL
+                    )::float8
+                    / NULLIF(COUNT(*) FILTER (WHERE "createdAt" >= $1 AND "createdAt" < $2), 0)::float8,
+                    0
+                )::float8 AS pending_never_confirmed_rate,
+                COUNT(*) FILTER (WHERE status = 'ACTIVE')::bigint AS active_total
+            FROM "NewsletterSubscription"
+        "#;
+
+        let summary_row: AnalyticsSummaryRow = sql_query(summary_sql)
+            .bind::<Timestamp, _>(range_start)
+            .bind::<Timestamp, _>(range_end)
+            .get_result(&mut conn)
+            .map_err(|e| format!("Summary query error: {}", e))?;
+
+        let series_sql = r#"
+            WITH buckets AS (
+                SELECT generate_series(
+                    date_trunc($3, $1::timestamp),
+                    date_trunc($3, $2::timestamp - interval '1 microsecond'),
+                    ('1 ' || $3)::interval
+                ) AS bucket
+            ),
+            signups AS (
+                SELECT date_trunc($3, "createdAt") AS bucket, COUNT(*) AS n
+                FROM "NewsletterSubscription"
+                WHERE "createdAt" >= $1 AND "createdAt" < $2
+                GROUP BY 1
+            ),
+            confirmations AS (
+                SELECT date_trunc($3, "confirmedAt") AS bucket, COUNT(*) AS n
+                FROM "NewsletterSubscription"
+                WHERE "confirmedAt" >= $1 AND "confirmedAt" < $2
+                GROUP BY 1
+            ),
+            unsubscribes AS (
+                SELECT date_trunc($3, "unsubscribedAt") AS bucket, COUNT(*) AS n
+                FROM "NewsletterSubscription"
+                WHERE "unsubscribedAt" >= $1 AND "unsubscribedAt" < $2
+                GROUP BY 1
+            )
+            SELECT
+                b.bucket AS bucket,
+                COALESCE(s.n, 0)::bigint AS signups,
+                COALESCE(c.n, 0)::bigint AS confirmations,
+                COALESCE(u.n, 0)::bigint AS unsubscribes
+            FROM buckets b
+            LEFT JOIN signups s ON s.bucket = b.bucket
+            LEFT JOIN confirmations c ON c.bucket = b.bucket
+            LEFT JOIN unsubscribes u ON u.bucket = b.bucket
+            ORDER BY b.bucket
+        "#;
+
+        let series_rows: Vec<AnalyticsBucketRow> = sql_query(series_sql)
+            .bind::<Timestamp, _>(range_start)
+            .bind::<Timestamp, _>(range_end)
+            .bind::<Text, _>(granularity)
+            .load(&mut conn)
+            .map_err(|e| format!("Series query error: {}", e))?;
+
+        let mut breakdowns = Vec::with_capacity(2);
+        for column in ["locale", "source"] {
+            let rows: Vec<AnalyticsBreakdownRow> = sql_query(breakdown_sql(column))
+                .bind::<Timestamp, _>(range_start)
+                .bind::<Timestamp, _>(range_end)
+                .load(&mut conn)
+                .map_err(|e| format!("Breakdown query error: {}", e))?;
+
+            breakdowns.push(
+                rows.into_iter()
+                    .map(|r| AnalyticsBreakdown {
+                        key: r.key,
+                        active: r.active,
+                        confirmations: r.confirmations,
+                        unsubscribes: r.unsubscribes,
+                    })
+                    .collect::<Vec<_>>(),
+            );
+        }
+        let by_source = breakdowns.pop().unwrap_or_default();
+        let by_locale = breakdowns.pop().unwrap_or_default();
+
+        let series = series_rows
+            .into_iter()
+            .map(|r| AnalyticsBucket {
+                period_start: r.bucket.date(),
+                signups: r.signups,
+                confirmations: r.confirmations,
+                unsubscribes: r.unsubscribes,
+                net_growth: r.confirmations - r.unsubscribes,
+            })
+            .collect::<Vec<_>>();
+
+        Ok::<_, String>(NewsletterAnalytics {
+            from,
+            to,
+            granularity: granularity.to_string(),
+            summary: AnalyticsSummary {
+                signups: summary_row.signups,
+                confirmations: summary_row.confirmations,
+                unsubscribes: summary_row.unsubscribes,
+                net_growth: summary_row.confirmations - summary_row.unsubscribes,
+                pending_never_confirmed: summary_row.pending_never_confirmed,
+                pending_never_confirmed_rate: summary_row.pending_never_confirmed_rate,
+                active_total: summary_row.active_total,
+            },
+            series,
+            by_locale,
+            by_source,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(data) => (StatusCode::OK, Json(Some(data))),
+        Err(e) => {
+            tracing::error!("newsletter analytics error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateIssueRequest {
+    pub user_role: String,
+    pub subject: String,
+    pub html: String,
+    pub text: Option<String>,
+    pub locale: Option<String>,
+    /// Adds an open pixel and click redirects for subscribers who haven't opted out.
+    pub tracking: Option<bool>,
+    /// Limits the issue to subscribers who chose this topic.
+    pub topic: Option<String>,
+    /// Hides the archive copy from readers without a paid membership.
+    pub members_only: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct ScheduleIssueRequest {
+    pub user_role: String,
+    pub issue_id: String,
+    /// RFC 3339 timestamp. Defaults to the next Saturday 09:00 KST.
+    pub send_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct IssueActionRequest {
+    pub user_role: String,
+    pub issue_id: String,
+}
+
+#[derive(Serialize)]
+pub struct IssueResponse {
+    pub success: bool,
+    pub message: String,
+    pub issue: Option<NewsletterIssue>,
+}
+
+impl IssueResponse {
+    fn error(message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+            issue: None,
+        }
+    }
+}
+
+fn parse_send_at(value: Option<&str>) -> Result<NaiveDateTime, String> {
+    let now = chrono::Utc::now();
+    match value.map(str::trim).filter(|v| !v.is_empty()) {
+        None => Ok(next_saturday_morning_kst(now)),
+        Some(v) => {
+            let at = chrono::DateTime::parse_from_rfc3339(v)
+                .map_err(|_| format!("Invalid send_at: {}", v))?
+                .naive_utc();
+            if at < now.naive_utc() - chrono::Duration::minutes(1) {
+                return Err("send_at is in the past".to_string());
+            }
+            Ok(at)
+        }
+    }
+}
+
+async fn create_issue(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateIssueRequest>,
+) -> (StatusCode, Json<IssueResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(IssueResponse::error("Unauthorized")));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(IssueResponse::error("Unauthorized")));
+    }
+
+    if payload.subject.trim().is_empty() || payload.html.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(IssueResponse::error("Subject and html are required")),
+        );
+    }
+
+    let topic = payload.topic.filter(|t| !t.trim().is_empty());
+    if topic.as_deref().is_some_and(|t| !TOPICS.contains(&t)) {
+        return (StatusCode::BAD_REQUEST, Json(IssueResponse::error("Invalid topic")));
+    }
+
+    let new_issue = NewNewsletterIssue {
+        id: cuid2::create_id(),
+        subject: payload.subject.trim().to_string(),
+        html: payload.html,
+        text: payload.text.filter(|t| !t.trim().is_empty()),
+        locale: if payload.locale.as_deref() == Some("en") { "en" } else { "ko" }.to_string(),
+        tracking_enabled: payload.tracking.unwrap_or(false),
+        kind: ISSUE_KIND_MANUAL.to_string(),
+        topic,
+        members_only: payload.members_only.unwrap_or(false),
+    };
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        diesel::insert_into(newsletter_issues::table)
+            .values(&new_issue)
+            .get_result::<NewsletterIssue>(&mut conn)
+            .map_err(|e| format!("DB insert error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(issue) => (
+            StatusCode::OK,
+            Json(IssueResponse {
+                success: true,
+                message: "Draft created".to_string(),
+                issue: Some(issue),
+            }),
+        ),
+        Err(e) => {
+            tracing::error!("create_issue error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(IssueResponse::error("Failed to create issue")),
+            )
+        }
+    }
+}
+
+async fn list_issues(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<AdminRequest>,
+) -> (StatusCode, Json<Vec<NewsletterIssue>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(vec![]));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(vec![]));
+    }
+
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        newsletter_issues::table
+            .order(newsletter_issues::created_at.desc())
+            .load::<NewsletterIssue>(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(issues) => (StatusCode::OK, Json(issues)),
+        Err(e) => {
+            tracing::error!("list_issues error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
+        }
+    }
+}
+
+enum IssueAction {
+    Schedule(NaiveDateTime),
+    Reschedule(NaiveDateTime),
+    Cancel,
+}
+
+fn apply_issue_action(
+    conn: &mut PgConnection,
+    issue_id: &str,
+    action: IssueAction,
+) -> Result<Result<NewsletterIssue, &'static str>, anyhow::Error> {
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        let issue: Option<NewsletterIssue> = newsletter_issues::table
+            .filter(newsletter_issues::id.eq(issue_id))
+            .for_update()
+            .first(conn)
+            .optional()?;
+
+        let Some(issue) = issue else {
+            return Ok(Err("Issue not found"));
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        let target = newsletter_issues::table.filter(newsletter_issues::id.eq(issue_id));
+
+        match action {
+            IssueAction::Schedule(run_at) => {
+                if issue.status != ISSUE_DRAFT {
+                    return Ok(Err("Only drafts can be scheduled"));
+                }
+
+                let payload = serde_json::to_value(SendIssuePayload {
+                    issue_id: issue_id.to_string(),
+                })?;
+                let job_id = jobs::enqueue(conn, jobs::KIND_NEWSLETTER_SEND, payload, run_at)?;
+
+                diesel::update(target)
+                    .set((
+                        newsletter_issues::status.eq(ISSUE_SCHEDULED),
+                        newsletter_issues::scheduled_at.eq(Some(run_at)),
+                        newsletter_issues::job_id.eq(Some(job_id)),
+                        newsletter_issues::updated_at.eq(now),
+                    ))
+                    .execute(conn)?;
+            }
+            IssueAction::Reschedule(run_at) => {
+                let Some(job_id) = issue.job_id.as_deref().filter(|_| issue.status == ISSUE_SCHEDULED)
+                else {
+                    return Ok(Err("Only scheduled issues can be rescheduled"));
+                };
+
+                if !jobs::reschedule(conn, job_id, run_at)? {
+                    return Ok(Err("Issue is already being sent"));
+                }
+
+                diesel::update(target)
+                    .set((
+                        newsletter_issues::scheduled_at.eq(Some(run_at)),
+                        newsletter_issues::updated_at.eq(now),
+                    ))
+                    .execute(conn)?;
+            }
+            IssueAction::Cancel => {
+                if issue.status != ISSUE_SCHEDULED && issue.status != ISSUE_SENDING {
+                    return Ok(Err("Only scheduled issues can be cancelled"));
+                }
+
+                if let Some(job_id) = issue.job_id.as_deref() {
+                    jobs::cancel(conn, job_id)?;
+                }
+
+                // Recipients already reached keep their delivery rows, so
+                // scheduling the draft again only mails the rest.
+                diesel::update(target)
+                    .set((
+                        newsletter_issues::status.eq(ISSUE_DRAFT),
+                        newsletter_issues::scheduled_at.eq::<Option<NaiveDateTime>>(None),
+                        newsletter_issues::job_id.eq::<Option<String>>(None),
+                        newsletter_issues::updated_at.eq(now),
+                    ))
+                    .execute(conn)?;
+            }
+        }
+
+        let issue: NewsletterIssue = newsletter_issues::table
+            .filter(newsletter_issues::id.eq(issue_id))
+            .first(conn)?;
+
+        Ok(Ok(issue))
+    })
+}
+
+async fn run_issue_action(
+    state: Arc<AppState>,
+    issue_id: String,
+    action: IssueAction,
+    success_message: &str,
+) -> (StatusCode, Json<IssueResponse>) {
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        apply_issue_action(&mut conn, &issue_id, action).map_err(|e| e.to_string())
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(Ok(issue)) => (
+            StatusCode::OK,
+            Json(IssueResponse {
+                success: true,
+                message: success_message.to_string(),
+                issue: Some(issue),
+            }),
+        ),
+        Ok(Err(message)) => (StatusCode::CONFLICT, Json(IssueResponse::error(message))),
+        Err(e) => {
+            tracing::error!("newsletter issue action error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(IssueResponse::error("Internal server error")),
+            )
+        }
+    }
+}
+
+async fn schedule_issue(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ScheduleIssueRequest>,
+) -> (StatusCode, Json<IssueResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(IssueResponse::error("Unauthorized")));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(IssueResponse::error("Unauthorized")));
+    }
+
+    let run_at = match parse_send_at(payload.send_at.as_deref()) {
+        Ok(at) => at,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(IssueResponse::error(&e))),
+    };
+
+    run_issue_action(state, payload.issue_id, IssueAction::Schedule(run_at), "Issue scheduled").await
+}
+
+async fn reschedule_issue(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ScheduleIssueRequest>,
+) -> (StatusCode, Json<IssueResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(IssueResponse::error("Unauthorized")));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(IssueResponse::error("Unauthorized")));
+    }
+
+    let run_at = match parse_send_at(payload.send_at.as_deref()) {
+        Ok(at) => at,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(IssueResponse::error(&e))),
+    };
+
+    run_issue_action(state, payload.issue_id, IssueAction::Reschedule(run_at), "Issue rescheduled").await
+}
+
+async fn cancel_issue(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<IssueActionRequest>,
+) -> (StatusCode, Json<IssueResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(IssueResponse::error("Unauthorized")));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(IssueResponse::error("Unauthorized")));
+    }
+
+    run_issue_action(state, payload.issue_id, IssueAction::Cancel, "Issue cancelled").await
+}
+
+#[derive(Serialize)]
+pub struct IssueLinkStats {
+    pub url: String,
+    pub clicks: i64,
+    pub unique_clicks: i64,
+}
+
+#[derive(Serialize)]
+pub struct IssueStatsResponse {
+    pub issue_id: String,
+    pub delivered: i64,
+    pub failed: i64,
+    /// Delivered with tracking; the denominator for open and click rates.
+    pub tracked: i64,
+    pub opens: i64,
+    pub unique_opens: i64,
+    pub clicks: i64,
+    pub unique_clicks: i64,
+    pub open_rate: f64,
+    pub click_rate: f64,
+    pub links: Vec<IssueLinkStats>,
+}
+
+#[derive(QueryableByName)]
+struct IssueStatsRow {
+    #[diesel(sql_type = BigInt)]
+    delivered: i64,
+    #[diesel(sql_type = BigInt)]
+    failed: i64,
+    #[diesel(sql_type = BigInt)]
+    tracked: i64,
+    #[diesel(sql_type = BigInt)]
+    opens: i64,
+    #[diesel(sql_type = BigInt)]
+    unique_opens: i64,
+    #[diesel(sql_type = BigInt)]
+    clicks: i64,
+    #[diesel(sql_type = BigInt)]
+    unique_clicks: i64,
+}
+
+#[derive(QueryableByName)]
+struct IssueLinkRow {
+    #[diesel(sql_type = Text)]
+    url: String,
+    #[diesel(sql_type = BigInt)]
+    clicks: i64,
+    #[diesel(sql_type = BigInt)]
+    unique_clicks: i64,
+}
+
+async fn issue_stats(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<IssueActionRequest>,
+) -> (StatusCode, Json<Option<IssueStatsResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(None));
+    }
+
+    let pool = state.db.clone();
+    let issue_id = payload.issue_id;
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let row: IssueStatsRow = sql_query(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM "NewsletterDelivery" WHERE "issueId" = $1 AND status = 'SENT')::bigint AS delivered,
+                (SELECT COUNT(*) FROM "NewsletterDelivery" WHERE "issueId" = $1 AND status = 'FAILED')::bigint AS failed,
+                (SELECT COUNT(*) FROM "NewsletterDelivery" WHERE "issueId" = $1 AND status = 'SENT' AND tracked)::bigint AS tracked,
+                COUNT(*) FILTER (WHERE type = 'open')::bigint AS opens,
+                COUNT(DISTINCT "deliveryId") FILTER (WHERE type = 'open')::bigint AS unique_opens,
+                COUNT(*) FILTER (WHERE type = 'click')::bigint AS clicks,
+                COUNT(DISTINCT "deliveryId") FILTER (WHERE type = 'click')::bigint AS unique_clicks
+            FROM "NewsletterEvent"
+            WHERE "issueId" = $1
+            "#,
+        )
+        .bind::<Text, _>(&issue_id)
+        .get_result(&mut conn)
+        .map_err(|e| format!("DB query error: {}", e))?;
+
+        let links: Vec<IssueLinkRow> = sql_query(
+            r#"
+            SELECT
+                url,
+                COUNT(*)::bigint AS clicks,
+                COUNT(DISTINCT "deliveryId")::bigint AS unique_clicks
+            FROM "NewsletterEvent"
+            WHERE "issueId" = $1 AND type = 'click' AND url IS NOT NULL
+            GROUP BY url
+            ORDER BY clicks DESC
+            "#,
+        )
+        .bind::<Text, _>(&issue_id)
+        .load(&mut conn)
+        .map_err(|e| format!("DB query error: {}", e))?;
+
+        let rate = |n: i64| if row.tracked > 0 { n as f64 / row.tracked as f64 } else { 0.0 };
+
+        Ok::<_, String>(IssueStatsResponse {
+            open_rate: rate(row.unique_opens),
+            click_rate: rate(row.unique_clicks),
+            issue_id,
+            delivered: row.delivered,
+            failed: row.failed,
+            tracked: row.tracked,
+            opens: row.opens,
+            unique_opens: row.unique_opens,
+            clicks: row.clicks,
+            unique_clicks: row.unique_clicks,
+            links: links
+                .into_iter()
+                .map(|l| IssueLinkStats {
+                    url: l.url,
+                    clicks: l.clicks,
+                    unique_clicks: l.unique_clicks,
+                })
+                .collect(),
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(stats) => (StatusCode::OK, Json(Some(stats))),
+        Err(e) => {
+            tracing::error!("issue_stats error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct GenerateDigestRequest {
+    pub user_role: String,
+    /// Only build this locale; both locales by default.
+    pub locale: Option<String>,
+    /// Overrides `NEWSLETTER_DIGEST_AUTO_SEND` for this run.
+    pub auto_send: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct GenerateDigestResponse {
+    pub success: bool,
+    pub message: String,
+    pub issues: Vec<NewsletterIssue>,
+}
+
+async fn generate_digest(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<GenerateDigestRequest>,
+) -> (StatusCode, Json<GenerateDigestResponse>) {
+    let error = |message: &str| GenerateDigestResponse {
+        success: false,
+        message: message.to_string(),
+        issues: vec![],
+    };
+
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(error("Unauthorized")));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(error("Unauthorized")));
+    }
+
+    let locales: Vec<&'static str> = match payload.locale.as_deref() {
+        None => vec!["ko", "en"],
+        Some("ko") => vec!["ko"],
+        Some("en") => vec!["en"],
+        Some(_) => return (StatusCode::BAD_REQUEST, Json(error("Invalid locale"))),
+    };
+    let pool = state.db.clone();
+    let engine = state.templates.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        let config = DigestConfig::from_env();
+        let auto_send = payload.auto_send.unwrap_or(config.auto_send);
+
+        let mut issues = Vec::new();
+        for locale in locales {
+            if let Some(issue) = digest::create_digest(&mut conn, &engine, locale, &config, auto_send)
+                .map_err(|e| format!("Digest error: {}", e))?
+            {
+                issues.push(issue);
+            }
+        }
+
+        Ok::<_, String>(issues)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(issues) => (
+            StatusCode::OK,
+            Json(GenerateDigestResponse {
+                success: true,
+                message: if issues.is_empty() {
+                    "No new posts since the last digest".to_string()
+                } else {
+                    format!("{} digest issue(s) created", issues.len())
+                },
+                issues,
+            }),
+        ),
+        Err(e) => {
+            tracing::error!("generate_digest error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error("Failed to generate digest")))
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/export", post(export_subscribers))
+        .route("/import", post(import_subscribers))
+        .route("/analytics", post(analytics))
+        .route("/issues", post(create_issue))
+        .route("/issues/list", post(list_issues))
+        .route("/issues/schedule", post(schedule_issue))
+        .route("/issues/reschedule", post(reschedule_issue))
+        .route("/issues/cancel", post(cancel_issue))
+        .route("/issues/stats", post(issue_stats))
+        .route("/digest", post(generate_digest))
+}
@@ -0,0 +1,220 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json,
+    Router,
+};
+use chrono::NaiveDateTime;
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::auth::verify_internal_api_key;
+use crate::schema::email_outbox;
+use crate::services::outbox::{self, RetryError, STATUS_DEAD, STATUS_PENDING, STATUS_SENDING, STATUS_SENT};
+use crate::services::AppState;
+
+#[derive(Deserialize)]
+pub struct ListOutboxRequest {
+    pub user_role: String,
+    /// Defaults to DEAD, the messages that need attention.
+    pub status: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct RetryOutboxRequest {
+    pub user_role: String,
+    pub id: Option<String>,
+    #[serde(default)]
+    pub all_dead: bool,
+}
+
+#[derive(Serialize, Queryable)]
+pub struct OutboxSummary {
+    pub id: String,
+    pub kind: String,
+    pub recipient: String,
+    pub subject: String,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub next_attempt_at: NaiveDateTime,
+    pub last_error: Option<String>,
+    pub sent_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct ListOutboxResponse {
+    pub counts: HashMap<String, i64>,
+    pub messages: Vec<OutboxSummary>,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[derive(Serialize)]
+pub struct RetryOutboxResponse {
+    pub success: bool,
+    pub message: String,
+    pub requeued: usize,
+}
+
+impl RetryOutboxResponse {
+    fn error(message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+            requeued: 0,
+        }
+    }
+}
+
+/// POST /api/admin/email-outbox/list
+async fn list_outbox(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ListOutboxRequest>,
+) -> (StatusCode, Json<Option<ListOutboxResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(None));
+    }
+
+    let status = payload.status.unwrap_or_else(|| STATUS_DEAD.to_string());
+    if ![STATUS_PENDING, STATUS_SENDING, STATUS_SENT, STATUS_DEAD].contains(&status.as_str()) {
+        return (StatusCode::BAD_REQUEST, Json(None));
+    }
+
+    let page = payload.page.unwrap_or(1).max(1);
+    let per_page = payload.per_page.unwrap_or(50).clamp(1, 200);
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let counts: Vec<(String, i64)> = email_outbox::table
+            .group_by(email_outbox::status)
+            .select((email_outbox::status, count_star()))
+            .load(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let messages: Vec<OutboxSummary> = email_outbox::table
+            .filter(email_outbox::status.eq(&status))
+            .order(email_outbox::updated_at.desc())
+            .limit(per_page)
+            .offset((page - 1) * per_page)
+            .select((
+                email_outbox::id,
+                email_outbox::kind,
+                email_outbox::recipient,
+                email_outbox::subject,
+                email_outbox::status,
+                email_outbox::attempts,
+                email_outbox::max_attempts,
+                email_outbox::next_attempt_at,
+                email_outbox::last_error,
+                email_outbox::sent_at,
+                email_outbox::created_at,
+            ))
+            .load(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        Ok::<_, String>(ListOutboxResponse {
+            counts: counts.into_iter().collect(),
+            messages,
+            page,
+            per_page,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(response) => (StatusCode::OK, Json(Some(response))),
+        Err(e) => {
+            tracing::error!("list_outbox error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// POST /api/admin/email-outbox/retry
+/// Requeues one message by `id`, or every dead message with `all_dead`.
+async fn retry_outbox(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<RetryOutboxRequest>,
+) -> (StatusCode, Json<RetryOutboxResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(RetryOutboxResponse::error("Unauthorized")));
+    }
+
+    if payload.user_role != "ADMIN" {
+        return (StatusCode::FORBIDDEN, Json(RetryOutboxResponse::error("Unauthorized")));
+    }
+
+    if payload.id.is_none() && !payload.all_dead {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(RetryOutboxResponse::error("Provide id or all_dead")),
+        );
+    }
+
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let retried = match payload.id {
+            Some(id) => outbox::retry(&mut conn, &id).map(usize::from),
+            None => outbox::retry_all_dead(&mut conn).map_err(RetryError::from),
+        };
+        match retried {
+            Ok(requeued) => Ok(Ok(requeued)),
+            Err(RetryError::Db(e)) => Err(format!("DB update error: {}", e)),
+            Err(e) => Ok(Err(e.to_string())),
+        }
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(Err(reason)) => (StatusCode::CONFLICT, Json(RetryOutboxResponse::error(&reason))),
+        Ok(Ok(0)) => (
+            StatusCode::CONFLICT,
+            Json(RetryOutboxResponse::error("Nothing to retry")),
+        ),
+        Ok(Ok(requeued)) => {
+            outbox::wake(&state);
+            (
+                StatusCode::OK,
+                Json(RetryOutboxResponse {
+                    success: true,
+                    message: "Requeued".to_string(),
+                    requeued,
+                }),
+            )
+        }
+        Err(e) => {
+            tracing::error!("retry_outbox error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(RetryOutboxResponse::error("Failed to retry")),
+            )
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/list", post(list_outbox))
+        .route("/retry", post(retry_outbox))
+}
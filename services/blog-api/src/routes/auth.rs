@@ -12,7 +12,10 @@ use chrono::{Utc, Duration};
 use sha2::{Sha256, Digest};
 
 use crate::services::AppState;
-use crate::services::email::EmailService;
+use crate::services::email::auth_from;
+use crate::services::mailer::OutgoingEmail;
+use crate::services::outbox;
+use crate::services::templates::{self, TemplateEngine, TemplateError};
 use crate::auth::{bearer_token, verify_supabase_jwt};
 use crate::schema::{verification_tokens, users, sessions};
 
@@ -262,6 +265,24 @@ fn hash_token(token: &str) -> String {
     hex::encode(hasher.finalize())
 }
 
+/// The sign-in email for `email`, linking back to `callback_url` with `raw_token`.
+fn magic_link_email(
+    templates: &TemplateEngine,
+    email: &str,
+    locale: &str,
+    callback_url: &str,
+    raw_token: &str,
+) -> Result<OutgoingEmail, TemplateError> {
+    let url = format!(
+        "{}/api/auth/callback/email?token={}&email={}",
+        callback_url.trim_end_matches('/'),
+        raw_token,
+        urlencoding::encode(email)
+    );
+    let content = templates.render(&templates::MagicLink { url }, locale)?;
+    Ok(OutgoingEmail::new(auth_from(), email.to_string(), content))
+}
+
 async fn send_magic_link(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<MagicLinkRequest>,
@@ -281,74 +302,72 @@ async fn send_magic_link(
         );
     }
 
+    if !state.mailer.is_configured() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(MagicLinkResponse {
+                success: false,
+                message: "Email service not configured".to_string(),
+            }),
+        );
+    }
+
     let raw_token = generate_token();
     let hashed_token = hash_token(&raw_token);
     let expires = Utc::now() + Duration::hours(24);
 
-    let conn = &mut match state.db.get() {
-        Ok(conn) => conn,
-        Err(_) => {
+    let message = match magic_link_email(&state.templates, &email, &locale, &callback_url, &raw_token) {
+        Ok(message) => message,
+        Err(e) => {
+            tracing::error!("Failed to render magic link email: {}", e);
             return (
-                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::INTERNAL_SERVER_ERROR,
                 Json(MagicLinkResponse {
                     success: false,
-                    message: "Database connection failed".to_string(),
+                    message: "Failed to send email".to_string(),
                 }),
             );
         }
     };
 
-    let insert_result = diesel::insert_into(verification_tokens::table)
-        .values((
-            verification_tokens::identifier.eq(&email),
-            verification_tokens::token.eq(&hashed_token),
-            verification_tokens::expires.eq(expires.naive_utc()),
-        ))
-        .execute(conn);
-
-    if insert_result.is_err() {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(MagicLinkResponse {
-                success: false,
-                message: "Failed to create verification token".to_string(),
-            }),
-        );
-    }
-
-    let magic_link_url = format!(
-        "{}/api/auth/callback/email?token={}&email={}",
-        callback_url.trim_end_matches('/'),
-        raw_token,
-        urlencoding::encode(&email)
-    );
-
-    let email_service = match EmailService::from_env() {
-        Ok(service) => service,
-        Err(e) => {
-            tracing::error!("Email service error: {}", e);
+    let conn = &mut match state.db.get() {
+        Ok(conn) => conn,
+        Err(_) => {
             return (
                 StatusCode::SERVICE_UNAVAILABLE,
                 Json(MagicLinkResponse {
                     success: false,
-                    message: "Email service not configured".to_string(),
+                    message: "Database connection failed".to_string(),
                 }),
             );
         }
     };
 
-    if let Err(e) = email_service.send_magic_link(&email, &magic_link_url, &locale).await {
-        tracing::error!("Failed to send magic link email: {}", e);
+    // The token only exists if its email is queued, and vice versa.
+    let insert_result = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::insert_into(verification_tokens::table)
+            .values((
+                verification_tokens::identifier.eq(&email),
+                verification_tokens::token.eq(&hashed_token),
+                verification_tokens::expires.eq(expires.naive_utc()),
+            ))
+            .execute(conn)?;
+        outbox::enqueue(conn, outbox::KIND_MAGIC_LINK, &message)
+    });
+
+    if let Err(e) = insert_result {
+        tracing::error!("Failed to queue magic link: {}", e);
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(MagicLinkResponse {
                 success: false,
-                message: "Failed to send email".to_string(),
+                message: "Failed to create verification token".to_string(),
             }),
         );
     }
 
-    tracing::info!("Magic link sent to {}", email);
+    outbox::wake(&state);
+    tracing::info!("Magic link queued for {}", email);
 
     (
         StatusCode::OK,
@@ -499,3 +518,47 @@ async fn verify_magic_link(
         }),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::mailer::{CaptureMailer, Mailer};
+
+    #[tokio::test]
+    async fn magic_link_email_reaches_the_mailer() {
+        let templates = TemplateEngine::new(None);
+        let message = magic_link_email(
+            &templates,
+            "reader+1@example.com",
+            "en",
+            "https://blog.example.com/",
+            "abc123",
+        )
+        .unwrap();
+
+        let mailer = CaptureMailer::new();
+        mailer.send(&message).await.unwrap();
+
+        let sent = mailer.captured();
+        assert_eq!(sent.len(), 1);
+        let link = "https:&#x2f;&#x2f;blog.example.com&#x2f;api&#x2f;auth&#x2f;callback&#x2f;email?token=abc123&amp;email=reader%2B1%40example.com";
+        assert_eq!(sent[0].to, "reader+1@example.com");
+        assert_eq!(sent[0].subject, "Sign in to Midnight Archives");
+        assert!(sent[0].html.contains(link), "{}", sent[0].html);
+        let text = sent[0].text.as_deref().unwrap();
+        assert!(
+            text.contains("https://blog.example.com/api/auth/callback/email?token=abc123&email=reader%2B1%40example.com"),
+            "{}",
+            text
+        );
+        assert!(text.contains("expires in 24 hours"), "{}", text);
+    }
+
+    #[test]
+    fn magic_link_email_follows_the_locale() {
+        let templates = TemplateEngine::new(None);
+        let message = magic_link_email(&templates, "a@example.com", "ko", "https://blog.example.com", "t").unwrap();
+        assert_ne!(message.subject, "Sign in to Midnight Archives");
+        assert!(message.html.contains("lang=\"ko\""), "{}", message.html);
+    }
+}
@@ -1,21 +1,33 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
     response::sse::{Event, KeepAlive, Sse},
     routing::post,
     Json,
     Router,
 };
-use futures::stream::Stream;
+use futures::stream::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::VecDeque,
     convert::Infallible,
+    net::{IpAddr, SocketAddr},
     sync::Arc,
     time::Duration,
 };
+use tokio::time::Instant;
 
-use crate::services::AppState;
+use super::search::SearchResult;
+use crate::auth::verify_internal_api_key;
+use crate::models::CachedAnswer;
+use crate::services::answer_cache::{self, CacheConfig, CacheKey};
+use crate::services::chat_guard::{self, GuardConfig, Screening};
+use crate::services::chat_history::{self, HistoryConfig, HistoryTurn};
+use crate::services::chat_quota::{self, QuotaConfig, QuotaError, QuotaStatus, Subject, Ticket};
+use crate::services::chat_stream::{StreamGuard, StreamOutcome, StreamTimeouts};
+use crate::services::sse::{SseEvent, SseParser, DEFAULT_EVENT};
+use crate::services::threads::{self, ThreadError};
+use crate::services::{AppState, DbPool};
 
 #[derive(Deserialize, Debug)]
 pub struct ChatRequest {
@@ -25,12 +37,30 @@ pub struct ChatRequest {
     #[serde(alias = "threadId")]
     pub thread_id: Option<String>,
     pub history: Option<serde_json::Value>,
+    /// Signed-in reader; trusted only with the internal API key.
+    #[serde(alias = "userId")]
+    pub user_id: Option<String>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct QuotaRequest {
+    /// Signed-in reader; trusted only with the internal API key.
+    #[serde(alias = "userId")]
+    pub user_id: Option<String>,
+}
+
+/// Body sent to the RAG service's `/api/chat`. `history` holds the earlier
+/// turns (see [`HistoryTurn`]), oldest first; it and `flags` are omitted
+/// when empty.
 #[derive(Serialize)]
 struct RagChatRequest<'a> {
     query: &'a str,
     locale: &'a str,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    history: &'a [HistoryTurn],
+    /// Screening rules that tagged the question (see [`chat_guard`]).
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    flags: &'a [&'static str],
 }
 
 #[derive(Serialize)]
@@ -40,12 +70,26 @@ pub struct ChatSimpleResponse {
     pub has_context: bool,
     #[serde(rename = "sourceCount")]
     pub source_count: usize,
+    /// The documents the answer was based on, in the `/api/search` shape.
+    pub sources: Vec<SearchResult>,
+    /// Fields of the upstream `done` event besides `type`, if it was seen.
+    pub done: Option<serde_json::Map<String, serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota: Option<QuotaStatus>,
+    /// Thread the turn was saved in, for a signed-in reader.
+    #[serde(rename = "threadId", skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    /// Id the answer was saved under, for feedback.
+    #[serde(rename = "messageId", skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct ErrorResponse {
     pub error: String,
     pub details: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota: Option<QuotaStatus>,
 }
 
 fn rag_base_url() -> Result<reqwest::Url, String> {
@@ -59,273 +103,1069 @@ fn rag_chat_url() -> Result<reqwest::Url, String> {
         .map_err(|e| format!("Invalid RAG chat URL: {e}"))
 }
 
-async fn chat(
-    State(_state): State<Arc<AppState>>,
-    Json(payload): Json<ChatRequest>,
-) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, Json<ErrorResponse>)> {
+fn error_response(status: StatusCode, error: &str, details: Option<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+            details,
+            quota: None,
+        }),
+    )
+}
+
+/// The reader the conversation is saved for, if any.
+pub(super) fn chat_reader(headers: &HeaderMap, user_id: Option<String>) -> Option<String> {
+    if verify_internal_api_key(headers).is_err() {
+        return None;
+    }
+    user_id.filter(|id| !id.trim().is_empty())
+}
+
+/// The address anonymous questions are counted against. Behind a reverse
+/// proxy, set `TRUST_PROXY_HEADERS=true` so `X-Real-IP` (or the last
+/// `X-Forwarded-For` hop) is used instead of the proxy's own address.
+fn client_ip(headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+    let trust_proxy = std::env::var("TRUST_PROXY_HEADERS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+
+    if trust_proxy {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let forwarded = header("x-real-ip")
+            .or_else(|| header("x-forwarded-for").and_then(|v| v.rsplit(',').next()))
+            .and_then(|v| v.trim().parse().ok());
+        if let Some(ip) = forwarded {
+            return ip;
+        }
+    }
+
+    peer.ip()
+}
+
+pub(super) fn quota_subject(reader: Option<&str>, headers: &HeaderMap, peer: SocketAddr) -> Subject {
+    match reader {
+        Some(user_id) => Subject::User(user_id.to_string()),
+        None => Subject::Ip(client_ip(headers, peer).to_string()),
+    }
+}
+
+fn quota_response(status: StatusCode, error: &str, details: Option<String>, quota: QuotaStatus) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+            details,
+            quota: Some(quota),
+        }),
+    )
+}
+
+/// Counts the question against today's quota: 429 once the free questions
+/// are gone and paying is not possible, 402 when the reader could pay with
+/// ink points but has too few. `None` when quotas are turned off.
+async fn take_quota(state: &AppState, subject: Subject) -> Result<Option<Ticket>, (StatusCode, Json<ErrorResponse>)> {
+    let config = QuotaConfig::from_env();
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let pool = state.db.clone();
+    let taken = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| QuotaError::Connection(e.to_string()))?;
+        chat_quota::consume(&mut conn, &subject, &config)
+    })
+    .await
+    .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "Task error", Some(e.to_string())))?;
+
+    match taken {
+        Ok(ticket) => Ok(Some(ticket)),
+        Err(QuotaError::Exhausted(quota)) => {
+            let details = (quota.plan == chat_quota::PLAN_ANONYMOUS)
+                .then(|| "Sign in for a larger daily allowance".to_string());
+            Err(quota_response(StatusCode::TOO_MANY_REQUESTS, "Daily chat limit reached", details, quota))
+        }
+        Err(QuotaError::InsufficientInk(quota)) => {
+            let details = quota
+                .ink_cost
+                .map(|cost| format!("Each question past the daily limit costs {} ink points", cost));
+            Err(quota_response(StatusCode::PAYMENT_REQUIRED, "Not enough ink points", details, quota))
+        }
+        Err(e) => {
+            tracing::error!("chat quota error: {}", e);
+            Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to check chat quota", None))
+        }
+    }
+}
+
+/// Gives the question back when no answer was started.
+fn refund_quota(state: &AppState, ticket: Option<Ticket>) {
+    let Some(ticket) = ticket else {
+        return;
+    };
+
+    let pool = state.db.clone();
+    tokio::task::spawn_blocking(move || {
+        let result = pool
+            .get()
+            .map_err(|e| format!("DB connection error: {}", e))
+            .and_then(|mut conn| chat_quota::refund(&mut conn, &ticket).map_err(|e| format!("DB update error: {}", e)));
+
+        if let Err(e) = result {
+            tracing::error!("Failed to refund chat quota: {}", e);
+        }
+    });
+}
+
+/// The `Last-Event-ID` a reconnecting client sent, forwarded upstream so
+/// the RAG service can resume after it.
+fn last_event_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+        .filter(|v| !v.contains('\0'))
+}
+
+/// The upstream `[DONE]` sentinel is not part of our protocol.
+fn is_done_sentinel(event: &SseEvent) -> bool {
+    event.data.trim() == "[DONE]"
+}
+
+/// Re-emits an upstream event with its name, id and retry intact.
+fn passthrough(event: &SseEvent) -> Event {
+    let mut out = Event::default().data(&event.data);
+    if event.event != DEFAULT_EVENT {
+        out = out.event(&event.event);
+    }
+    if let Some(id) = &event.id {
+        out = out.id(id);
+    }
+    if let Some(retry) = event.retry {
+        out = out.retry(Duration::from_millis(retry));
+    }
+    out
+}
+
+/// One event of an answer, before it is framed for SSE or a WebSocket.
+pub(super) enum AnswerEvent {
+    /// Relayed from the RAG service as received.
+    Upstream(SseEvent),
+    /// Produced here: thread, quota, error and replayed events.
+    Local(serde_json::Value),
+}
+
+impl AnswerEvent {
+    fn into_sse(self) -> Event {
+        match self {
+            AnswerEvent::Upstream(event) => passthrough(&event),
+            AnswerEvent::Local(value) => Event::default().data(value.to_string()),
+        }
+    }
+
+    /// The event's JSON payload. Upstream events that aren't JSON are
+    /// `None`.
+    pub(super) fn value(&self) -> Option<serde_json::Value> {
+        match self {
+            AnswerEvent::Upstream(event) => serde_json::from_str(&event.data).ok(),
+            AnswerEvent::Local(value) => Some(value.clone()),
+        }
+    }
+}
+
+/// One entry of an upstream `sources` event. Malformed entries are skipped.
+fn source_from(value: &serde_json::Value, locale: &str) -> Option<SearchResult> {
+    match serde_json::from_value::<SearchResult>(value.clone()) {
+        Ok(mut source) => {
+            if source.locale.is_empty() {
+                source.locale = locale.to_string();
+            }
+            Some(source)
+        }
+        Err(e) => {
+            tracing::warn!("Skipping malformed RAG source: {}", e);
+            None
+        }
+    }
+}
+
+pub(super) fn event_type(value: &serde_json::Value) -> Option<&str> {
+    value.get("type").and_then(|v| v.as_str())
+}
+
+/// Last event of a stream that could not finish, in place of an answer.
+pub(super) fn error_event(code: &str, message: &str) -> AnswerEvent {
+    AnswerEvent::Local(serde_json::json!({ "type": "error", "code": code, "message": message }))
+}
+
+/// Sends the question and waits for response headers, at most until
+/// `deadline` (the first-token budget). Failures are recorded on `guard`.
+async fn send_upstream(
+    url: reqwest::Url,
+    body: &RagChatRequest<'_>,
+    resume_from: Option<&str>,
+    timeouts: &StreamTimeouts,
+    deadline: Instant,
+    guard: &mut StreamGuard,
+) -> Result<reqwest::Response, (StatusCode, Json<ErrorResponse>)> {
+    let client = match reqwest::Client::builder().connect_timeout(timeouts.connect).build() {
+        Ok(client) => client,
+        Err(e) => {
+            guard.finish(StreamOutcome::Failed);
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create HTTP client", Some(e.to_string())));
+        }
+    };
+
+    let mut request = client.post(url).json(body);
+    if let Some(id) = resume_from {
+        request = request.header("Last-Event-ID", id);
+    }
+
+    let response = match tokio::time::timeout_at(deadline, request.send()).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) if e.is_timeout() => {
+            guard.finish(StreamOutcome::TimedOut);
+            return Err(error_response(StatusCode::GATEWAY_TIMEOUT, "RAG service timed out", Some(e.to_string())));
+        }
+        Ok(Err(e)) => {
+            guard.finish(StreamOutcome::Failed);
+            return Err(error_response(StatusCode::BAD_GATEWAY, "Failed to reach RAG service", Some(e.to_string())));
+        }
+        Err(_) => {
+            guard.finish(StreamOutcome::TimedOut);
+            return Err(error_response(StatusCode::GATEWAY_TIMEOUT, "RAG service timed out", None));
+        }
+    };
+
+    if !response.status().is_success() {
+        guard.finish(StreamOutcome::Failed);
+        let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+        let details = response.text().await.ok();
+        return Err(error_response(status, "RAG service error", details));
+    }
+
+    Ok(response)
+}
+
+/// Waits for the next upstream chunk: until `deadline` while no content has
+/// arrived yet, then at most the idle timeout. `None` means it timed out.
+async fn next_chunk(
+    response: &mut reqwest::Response,
+    first_token: bool,
+    deadline: Instant,
+    timeouts: &StreamTimeouts,
+) -> Option<reqwest::Result<Option<axum::body::Bytes>>> {
+    let wait = if first_token {
+        Instant::now() + timeouts.idle
+    } else {
+        deadline
+    };
+    tokio::time::timeout_at(wait, response.chunk()).await.ok()
+}
+
+/// Fields of a `done` event besides `type`.
+fn done_fields(value: &serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+    let mut fields = match value {
+        serde_json::Value::Object(fields) => fields.clone(),
+        _ => serde_json::Map::new(),
+    };
+    fields.remove("type");
+    fields
+}
+
+/// Where a streamed answer goes once it has ended: the reader's thread,
+/// the answer cache, or both. The answer is recorded when the recorder is
+/// dropped, so a stream cut short by the client, the RAG service or a
+/// timeout still closes the reader's turn.
+struct AnswerRecorder {
+    pool: Arc<DbPool>,
+    /// Thread and pre-allocated message id of the reader's turn.
+    turn: Option<(String, String)>,
+    cache: Option<CacheKey>,
+    cache_ttl: Duration,
+    content: String,
+    sources: Option<serde_json::Value>,
+    done: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Set once the upstream stream ended cleanly.
+    completed: bool,
+}
+
+impl AnswerRecorder {
+    fn observe(&mut self, value: &serde_json::Value) {
+        match event_type(value) {
+            Some("content") => {
+                if let Some(content) = value.get("content").and_then(|v| v.as_str()) {
+                    self.content.push_str(content);
+                }
+            }
+            Some("sources") => {
+                self.sources = value.get("sources").cloned();
+            }
+            Some("done") => {
+                self.done = Some(done_fields(value));
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for AnswerRecorder {
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            tracing::error!("Failed to store chat answer: no runtime");
+            return;
+        };
+
+        let pool = self.pool.clone();
+        let turn = self.turn.take();
+        let cache = self.cache.take();
+        let cache_ttl = self.cache_ttl;
+        let content = std::mem::take(&mut self.content);
+        let sources = self.sources.take();
+        let done = self.done.take();
+        let complete = self.completed;
+
+        runtime.spawn_blocking(move || {
+            let mut conn = match pool.get() {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::error!("Failed to store chat answer: DB connection error: {}", e);
+                    return;
+                }
+            };
+
+            if let Some((thread_id, message_id)) = &turn {
+                // A question with nothing streamed back is taken back rather
+                // than left in the thread without an answer.
+                let result = if content.trim().is_empty() && !complete {
+                    threads::abandon_turn(&mut conn, thread_id)
+                } else {
+                    threads::finish_turn(&mut conn, thread_id, message_id, &content, sources.clone(), !complete)
+                };
+                if let Err(e) = result {
+                    tracing::error!("Failed to store answer for thread {}: {}", thread_id, e);
+                }
+            }
+
+            // Only complete answers are worth replaying.
+            let (Some(key), Some(done), true) = (&cache, done, complete) else {
+                return;
+            };
+            if content.trim().is_empty() {
+                return;
+            }
+            let done = serde_json::Value::Object(done);
+            if let Err(e) = answer_cache::store(&mut conn, key, &content, sources, Some(done), cache_ttl) {
+                tracing::error!("Failed to cache chat answer: {}", e);
+            }
+        });
+    }
+}
+
+/// The cached answer to a first question, if there is a live one. Cache
+/// errors are logged and treated as a miss.
+async fn cached_answer(state: &AppState, key: Option<&CacheKey>) -> Option<CachedAnswer> {
+    let key = key?.clone();
+    let pool = state.db.clone();
+
+    let found = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        answer_cache::lookup(&mut conn, &key).map_err(|e| format!("DB query error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match found {
+        Ok(Some(hit)) => {
+            state.chat_metrics.record_cache_hit();
+            Some(hit)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            tracing::warn!("chat answer cache lookup failed: {}", e);
+            None
+        }
+    }
+}
+
+/// The cached `done` fields, marked so clients and logs can tell a replay.
+fn cached_done(hit: &CachedAnswer) -> serde_json::Map<String, serde_json::Value> {
+    let mut done = hit.done.as_ref().map(done_fields).unwrap_or_default();
+    done.insert("cached".to_string(), serde_json::Value::Bool(true));
+    done
+}
+
+/// Stores a reader's question to a thread before it is answered. Returns
+/// the thread id.
+async fn begin_turn(
+    state: &AppState,
+    user_id: String,
+    requested_thread: Option<String>,
+    query: &str,
+) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+    let pool = state.db.clone();
+    let question = query.to_string();
+
+    let started = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| ThreadError::Connection(e.to_string()))?;
+        threads::start_turn(&mut conn, &user_id, requested_thread.as_deref(), &question)
+    })
+    .await
+    .unwrap_or_else(|e| Err(ThreadError::Connection(format!("Task error: {}", e))));
+
+    match started {
+        Ok(id) => Ok(id),
+        Err(ThreadError::NotFound) => Err(error_response(StatusCode::NOT_FOUND, "Thread not found", None)),
+        Err(e) => {
+            tracing::error!("chat start_turn error: {}", e);
+            Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save conversation", None))
+        }
+    }
+}
+
+/// Stores a reader's question together with its cached answer, as if it
+/// had been answered live. Returns the thread and answer message ids.
+async fn store_cached_turn(
+    state: &AppState,
+    user_id: String,
+    requested_thread: Option<String>,
+    query: &str,
+    hit: &CachedAnswer,
+) -> Result<(String, String), (StatusCode, Json<ErrorResponse>)> {
+    let pool = state.db.clone();
+    let question = query.to_string();
+    let answer = hit.answer.clone();
+    let sources = hit.sources.clone();
+    let message_id = cuid2::create_id();
+    let stored_id = message_id.clone();
+
+    let stored = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| ThreadError::Connection(e.to_string()))?;
+        let thread_id = threads::start_turn(&mut conn, &user_id, requested_thread.as_deref(), &question)?;
+        threads::finish_turn(&mut conn, &thread_id, &stored_id, &answer, sources, false)?;
+        Ok::<_, ThreadError>(thread_id)
+    })
+    .await
+    .unwrap_or_else(|e| Err(ThreadError::Connection(format!("Task error: {}", e))));
+
+    match stored {
+        Ok(thread_id) => Ok((thread_id, message_id)),
+        Err(ThreadError::NotFound) => Err(error_response(StatusCode::NOT_FOUND, "Thread not found", None)),
+        Err(e) => {
+            tracing::error!("chat cached turn error: {}", e);
+            Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save conversation", None))
+        }
+    }
+}
+
+/// Answers a first question from the cache. A reader's turn is stored as
+/// if it had been answered live; the answer is then replayed as the events
+/// a live answer produces, content in small paced pieces.
+async fn replay_cached(
+    state: &AppState,
+    reader: Option<String>,
+    requested_thread: Option<String>,
+    query: &str,
+    hit: CachedAnswer,
+    config: &CacheConfig,
+) -> Result<impl Stream<Item = AnswerEvent>, (StatusCode, Json<ErrorResponse>)> {
+    let mut events = Vec::new();
+
+    if let Some(user_id) = reader {
+        let (thread_id, message_id) = store_cached_turn(state, user_id, requested_thread, query, &hit).await?;
+
+        let thread = serde_json::json!({
+            "type": "thread",
+            "threadId": thread_id,
+            "messageId": message_id,
+        });
+        events.push((AnswerEvent::Local(thread), false));
+    }
+
+    if let Some(sources) = &hit.sources {
+        let sources = serde_json::json!({ "type": "sources", "sources": sources });
+        events.push((AnswerEvent::Local(sources), false));
+    }
+    for chunk in answer_cache::replay_chunks(&hit.answer, config.replay_chunk_chars) {
+        let content = serde_json::json!({ "type": "content", "content": chunk });
+        events.push((AnswerEvent::Local(content), true));
+    }
+    let mut done = cached_done(&hit);
+    done.insert("type".to_string(), serde_json::Value::from("done"));
+    events.push((AnswerEvent::Local(serde_json::Value::Object(done)), false));
+
+    let delay = config.replay_delay;
+    Ok(futures::stream::iter(events).then(move |(event, paced)| async move {
+        if paced && !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        event
+    }))
+}
+
+/// Screens a question before anything else sees it: refuses it when a
+/// blocking rule fires, and otherwise returns it with personal data
+/// redacted. Rule hits are logged in the background.
+fn screen_query(
+    state: &AppState,
+    reader: Option<&str>,
+    query: String,
+    locale: &str,
+) -> Result<Screening, (StatusCode, Json<ErrorResponse>)> {
+    let config = GuardConfig::from_env();
+    if !config.enabled {
+        return Ok(Screening {
+            query,
+            hits: Vec::new(),
+            blocked_by: None,
+            flags: Vec::new(),
+        });
+    }
+
+    let screening = chat_guard::screen(&query, &config);
+    if !screening.hits.is_empty() {
+        let pool = state.db.clone();
+        let hits = screening.hits.clone();
+        let user_id = reader.map(|id| id.to_string());
+        let locale = locale.to_string();
+        let logged = screening.query.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let result = pool
+                .get()
+                .map_err(|e| format!("DB connection error: {}", e))
+                .and_then(|mut conn| {
+                    chat_guard::log_hits(&mut conn, &hits, user_id.as_deref(), &locale, &logged)
+                        .map_err(|e| format!("DB insert error: {}", e))
+                });
+
+            if let Err(e) = result {
+                tracing::error!("Failed to log chat guard hits: {}", e);
+            }
+        });
+    }
+
+    match screening.blocked_by {
+        Some(chat_guard::RULE_MAX_LENGTH) => Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "Query is too long",
+            Some(format!("Questions are limited to {} characters", config.max_query_chars)),
+        )),
+        Some(_) => Err(error_response(StatusCode::UNPROCESSABLE_ENTITY, "Question was declined", None)),
+        None => Ok(screening),
+    }
+}
+
+/// Earlier turns for the RAG request: the stored thread when a reader
+/// continues one of their threads, otherwise the client's `history`.
+async fn resolve_history(
+    state: &AppState,
+    reader: Option<&str>,
+    thread_id: Option<&str>,
+    client_history: Option<&serde_json::Value>,
+) -> Result<Vec<HistoryTurn>, (StatusCode, Json<ErrorResponse>)> {
+    let config = HistoryConfig::from_env();
+
+    let turns = match (reader, thread_id) {
+        (Some(user_id), Some(thread_id)) => {
+            let pool = state.db.clone();
+            let user_id = user_id.to_string();
+            let thread_id = thread_id.to_string();
+            let limit = config.max_turns;
+
+            let loaded = tokio::task::spawn_blocking(move || {
+                let mut conn = pool.get().map_err(|e| ThreadError::Connection(e.to_string()))?;
+                let thread_id = threads::owned_chat_thread(&mut conn, &user_id, &thread_id)?;
+                Ok::<_, ThreadError>(chat_history::load_thread(&mut conn, &thread_id, limit)?)
+            })
+            .await
+            .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, "Task error", Some(e.to_string())))?;
+
+            match loaded {
+                Ok(turns) => turns,
+                Err(ThreadError::NotFound) => {
+                    return Err(error_response(StatusCode::NOT_FOUND, "Thread not found", None));
+                }
+                Err(e) => {
+                    tracing::error!("chat history error: {}", e);
+                    return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to load conversation", None));
+                }
+            }
+        }
+        _ => match client_history {
+            Some(value) if !value.is_null() => chat_history::from_client(value)
+                .map_err(|e| error_response(StatusCode::BAD_REQUEST, "Invalid history", Some(e)))?,
+            _ => Vec::new(),
+        },
+    };
+
+    let mut turns = chat_history::trim(turns, &config);
+    if GuardConfig::from_env().enabled {
+        for turn in &mut turns {
+            turn.content = chat_guard::redact(&turn.content);
+        }
+    }
+    Ok(turns)
+}
+
+/// Answers one question: checks the cache and quota, stores the reader's
+/// turn and proxies the RAG stream. Shared by the SSE and WebSocket
+/// transports; `reader` and `subject` come from the caller's credentials.
+pub(super) async fn answer(
+    state: &AppState,
+    reader: Option<String>,
+    subject: Subject,
+    resume_from: Option<String>,
+    payload: ChatRequest,
+) -> Result<impl Stream<Item = AnswerEvent> + Send + 'static, (StatusCode, Json<ErrorResponse>)> {
     let query = payload.query.unwrap_or_default();
     let query = query.trim().to_string();
     if query.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Query is required".to_string(),
-                details: None,
-            }),
-        ));
+        return Err(error_response(StatusCode::BAD_REQUEST, "Query is required", None));
     }
 
     let locale = payload.locale.unwrap_or_else(|| "ko".to_string());
     let locale = if locale == "en" { "en" } else { "ko" };
 
+    // A reconnect continues an answer that was already paid for and
+    // stored: the RAG service replays the rest of it, and nothing here is
+    // screened, charged, cached or stored again.
+    let resuming = resume_from.is_some();
+
+    let (query, flags) = if resuming {
+        (query, Vec::new())
+    } else {
+        let screening = screen_query(state, reader.as_deref(), query, locale)?;
+        (screening.query, screening.flags)
+    };
+
     let url = rag_chat_url().map_err(|e| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "RAG service is not configured".to_string(),
-                details: Some(e),
-            }),
-        )
+        error_response(StatusCode::SERVICE_UNAVAILABLE, "RAG service is not configured", Some(e))
     })?;
 
-    let client = reqwest::Client::new();
-    let upstream = client
-        .post(url)
-        .json(&RagChatRequest {
-            query: &query,
-            locale,
-        })
-        .send()
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::BAD_GATEWAY,
-                Json(ErrorResponse {
-                    error: "Failed to reach RAG service".to_string(),
-                    details: Some(e.to_string()),
-                }),
-            )
-        })?;
-
-    if !upstream.status().is_success() {
-        let status = StatusCode::from_u16(upstream.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
-        let details = upstream.text().await.ok();
-        return Err((
-            status,
-            Json(ErrorResponse {
-                error: "RAG service error".to_string(),
-                details,
-            }),
-        ));
+    let history = if resuming {
+        Vec::new()
+    } else {
+        resolve_history(
+            state,
+            reader.as_deref(),
+            payload.thread_id.as_deref(),
+            payload.history.as_ref(),
+        )
+        .await?
+    };
+
+    // Only first questions are cached; follow-ups depend on the conversation.
+    // Cached answers are free.
+    let cache_config = CacheConfig::from_env();
+    let cache_key =
+        (cache_config.enabled && !resuming && history.is_empty()).then(|| CacheKey::new(&query, locale));
+    if let Some(hit) = cached_answer(state, cache_key.as_ref()).await {
+        let replay = replay_cached(state, reader, payload.thread_id, &query, hit, &cache_config).await?;
+        return Ok(replay.left_stream());
     }
 
+    let ticket = if resuming { None } else { take_quota(state, subject).await? };
+    let quota = ticket.as_ref().map(|ticket| ticket.status.clone());
+
+    let thread_id = match reader.filter(|_| !resuming) {
+        Some(user_id) => match begin_turn(state, user_id, payload.thread_id.clone(), &query).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                refund_quota(state, ticket);
+                return Err(e);
+            }
+        },
+        None => None,
+    };
+
+    // Created before the upstream request so that a failed request takes
+    // the reader's question back out of the thread.
+    let turn = thread_id.map(|thread_id| (thread_id, cuid2::create_id()));
+    let recorder = (turn.is_some() || cache_key.is_some()).then(|| AnswerRecorder {
+        pool: state.db.clone(),
+        turn,
+        cache: cache_key,
+        cache_ttl: cache_config.ttl,
+        content: String::new(),
+        sources: None,
+        done: None,
+        completed: false,
+    });
+
+    let timeouts = StreamTimeouts::from_env();
+    let deadline = Instant::now() + timeouts.first_token;
+    let mut guard = StreamGuard::start(state.chat_metrics.clone());
+
+    let body = RagChatRequest {
+        query: &query,
+        locale,
+        history: &history,
+        flags: &flags,
+    };
+    let upstream = match send_upstream(url, &body, resume_from.as_deref(), &timeouts, deadline, &mut guard).await {
+        Ok(response) => response,
+        Err(e) => {
+            refund_quota(state, ticket);
+            return Err(e);
+        }
+    };
+
+    // Dropping this state (the client disconnected) drops the upstream
+    // response, which closes the connection and stops generation.
     struct ProxyState {
         response: reqwest::Response,
-        buffer: String,
-        pending: VecDeque<String>,
+        parser: SseParser,
+        pending: VecDeque<AnswerEvent>,
         ended: bool,
+        recorder: Option<AnswerRecorder>,
+        timeouts: StreamTimeouts,
+        deadline: Instant,
+        first_token: bool,
+        guard: StreamGuard,
+    }
+
+    // Tell the client which thread to continue with, the id the answer will
+    // be stored under (for feedback), and what is left of today's quota.
+    let mut pending = VecDeque::new();
+    if let Some((thread_id, message_id)) = recorder.as_ref().and_then(|r| r.turn.as_ref()) {
+        let thread = serde_json::json!({
+            "type": "thread",
+            "threadId": thread_id,
+            "messageId": message_id,
+        });
+        pending.push_back(AnswerEvent::Local(thread));
+    }
+    if let Some(quota) = &quota {
+        pending.push_back(AnswerEvent::Local(serde_json::json!({ "type": "quota", "quota": quota })));
     }
 
     let stream = futures::stream::unfold(
         ProxyState {
             response: upstream,
-            buffer: String::new(),
-            pending: VecDeque::new(),
+            parser: match &resume_from {
+                Some(id) => SseParser::with_last_event_id(id),
+                None => SseParser::new(),
+            },
+            pending,
             ended: false,
+            recorder,
+            timeouts,
+            deadline,
+            first_token: false,
+            guard,
         },
         |mut state| async move {
             loop {
                 if let Some(next) = state.pending.pop_front() {
-                    return Some((Ok(Event::default().data(next)), state));
+                    return Some((next, state));
                 }
 
                 if state.ended {
                     return None;
                 }
 
-                match state.response.chunk().await {
-                    Ok(Some(bytes)) => {
-                        let chunk = String::from_utf8_lossy(&bytes);
-
-                        let mut buffer = std::mem::take(&mut state.buffer);
-                        buffer.push_str(&chunk);
-
-                        let mut lines = buffer.split('\n').collect::<Vec<_>>();
-                        state.buffer = lines.pop().unwrap_or("").to_string();
-
-                        for line in lines {
-                            if let Some(data) = line.strip_prefix("data: ") {
-                                let data = data.trim();
-                                if data.is_empty() || data == "[DONE]" {
-                                    continue;
+                let chunk = next_chunk(&mut state.response, state.first_token, state.deadline, &state.timeouts).await;
+                match chunk {
+                    Some(Ok(Some(bytes))) => {
+                        for event in state.parser.feed(&bytes) {
+                            if is_done_sentinel(&event) {
+                                continue;
+                            }
+                            if let Ok(value) = serde_json::from_str::<serde_json::Value>(&event.data) {
+                                if event_type(&value) == Some("content") {
+                                    state.first_token = true;
+                                }
+                                if let Some(recorder) = state.recorder.as_mut() {
+                                    recorder.observe(&value);
                                 }
-                                state.pending.push_back(data.to_string());
                             }
+                            state.pending.push_back(AnswerEvent::Upstream(event));
                         }
 
                         continue;
                     }
-                    Ok(None) => return None,
-                    Err(e) => {
+                    Some(Ok(None)) => {
+                        state.guard.finish(StreamOutcome::Completed);
+                        if let Some(recorder) = state.recorder.take() {
+                            recorder.finish();
+                        }
+                        return None;
+                    }
+                    Some(Err(e)) => {
                         state.ended = true;
+                        state.guard.finish(StreamOutcome::Failed);
+                        tracing::warn!(
+                            "RAG stream failed (last event id {:?}): {}",
+                            state.parser.last_event_id(),
+                            e
+                        );
 
-                        let payload = serde_json::json!({
-                            "type": "content",
-                            "content": format!("[RAG proxy error] {e}"),
-                        })
-                        .to_string();
+                        return Some((error_event("upstream", "The answer stream was interrupted"), state));
+                    }
+                    None => {
+                        state.ended = true;
+                        state.guard.finish(StreamOutcome::TimedOut);
+                        let message = if state.first_token {
+                            "The answer stream stalled"
+                        } else {
+                            "The answer took too long to start"
+                        };
+                        tracing::warn!("RAG stream timed out after {} ms: {}", state.guard.elapsed().as_millis(), message);
 
-                        return Some((Ok(Event::default().data(payload)), state));
+                        return Some((error_event("timeout", message), state));
                     }
                 }
             }
         },
     );
 
-    Ok(Sse::new(stream).keep_alive(
+    Ok(stream.right_stream())
+}
+
+async fn chat(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(mut payload): Json<ChatRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, Json<ErrorResponse>)> {
+    let reader = chat_reader(&headers, payload.user_id.take());
+    let subject = quota_subject(reader.as_deref(), &headers, peer);
+    let timeouts = StreamTimeouts::from_env();
+
+    let events = answer(&state, reader, subject, last_event_id(&headers), payload).await?;
+
+    Ok(Sse::new(events.map(|event| Ok(event.into_sse()))).keep_alive(
         KeepAlive::new()
-            .interval(Duration::from_secs(15))
+            .interval(timeouts.heartbeat)
             .text("keep-alive"),
     ))
 }
 
 async fn chat_simple(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<ChatRequest>,
 ) -> Result<(StatusCode, Json<ChatSimpleResponse>), (StatusCode, Json<ErrorResponse>)> {
     let query = payload.query.unwrap_or_default();
     let query = query.trim().to_string();
     if query.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Query is required".to_string(),
-                details: None,
-            }),
-        ));
+        return Err(error_response(StatusCode::BAD_REQUEST, "Query is required", None));
     }
 
     let locale = payload.locale.unwrap_or_else(|| "ko".to_string());
     let locale = if locale == "en" { "en" } else { "ko" };
 
+    let reader = chat_reader(&headers, payload.user_id);
+    let screening = screen_query(&state, reader.as_deref(), query, locale)?;
+    let query = screening.query;
+
     let url = rag_chat_url().map_err(|e| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse {
-                error: "RAG service is not configured".to_string(),
-                details: Some(e),
-            }),
-        )
+        error_response(StatusCode::SERVICE_UNAVAILABLE, "RAG service is not configured", Some(e))
     })?;
 
-    let client = reqwest::Client::new();
-    let mut upstream = client
-        .post(url)
-        .json(&RagChatRequest {
-            query: &query,
-            locale,
-        })
-        .send()
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::BAD_GATEWAY,
-                Json(ErrorResponse {
-                    error: "Failed to reach RAG service".to_string(),
-                    details: Some(e.to_string()),
-                }),
-            )
-        })?;
-
-    if !upstream.status().is_success() {
-        let status = StatusCode::from_u16(upstream.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
-        let details = upstream.text().await.ok();
-        return Err((
-            status,
-            Json(ErrorResponse {
-                error: "RAG service error".to_string(),
-                details,
+    let history = resolve_history(
+        &state,
+        reader.as_deref(),
+        payload.thread_id.as_deref(),
+        payload.history.as_ref(),
+    )
+    .await?;
+
+    let cache_config = CacheConfig::from_env();
+    let cache_key = (cache_config.enabled && history.is_empty()).then(|| CacheKey::new(&query, locale));
+    if let Some(hit) = cached_answer(&state, cache_key.as_ref()).await {
+        let turn = match reader {
+            Some(user_id) => Some(store_cached_turn(&state, user_id, payload.thread_id, &query, &hit).await?),
+            None => None,
+        };
+        let sources: Vec<SearchResult> = hit
+            .sources
+            .as_ref()
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|item| source_from(item, locale)).collect())
+            .unwrap_or_default();
+        let done = Some(cached_done(&hit));
+
+        return Ok((
+            StatusCode::OK,
+            Json(ChatSimpleResponse {
+                response: hit.answer,
+                has_context: !sources.is_empty(),
+                source_count: sources.len(),
+                sources,
+                done,
+                quota: None,
+                thread_id: turn.as_ref().map(|(thread_id, _)| thread_id.clone()),
+                message_id: turn.map(|(_, message_id)| message_id),
             }),
         ));
     }
 
-    let mut buffer = String::new();
-    let mut response_text = String::new();
-    let mut source_count: usize = 0;
-    let mut done = false;
+    let ticket = take_quota(&state, quota_subject(reader.as_deref(), &headers, peer)).await?;
+    let quota = ticket.as_ref().map(|ticket| ticket.status.clone());
 
-    loop {
-        match upstream.chunk().await {
-            Ok(Some(bytes)) => {
-                let chunk = String::from_utf8_lossy(&bytes);
+    let thread_id = match reader {
+        Some(user_id) => match begin_turn(&state, user_id, payload.thread_id, &query).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                refund_quota(&state, ticket);
+                return Err(e);
+            }
+        },
+        None => None,
+    };
 
-                let mut combined = std::mem::take(&mut buffer);
-                combined.push_str(&chunk);
+    // Stores the turn and caches the answer the way `/api/chat` does; a
+    // failed request takes the question back out of the thread.
+    let turn = thread_id.map(|thread_id| (thread_id, cuid2::create_id()));
+    let mut recorder = AnswerRecorder {
+        pool: state.db.clone(),
+        turn: turn.clone(),
+        cache: cache_key,
+        cache_ttl: cache_config.ttl,
+        content: String::new(),
+        sources: None,
+        done: None,
+        completed: false,
+    };
 
-                let mut lines = combined.split('\n').collect::<Vec<_>>();
-                buffer = lines.pop().unwrap_or("").to_string();
+    let timeouts = StreamTimeouts::from_env();
+    let deadline = Instant::now() + timeouts.first_token;
+    let mut guard = StreamGuard::start(state.chat_metrics.clone());
 
-                for line in lines {
-                    let Some(data) = line.strip_prefix("data: ") else {
-                        continue;
-                    };
+    let body = RagChatRequest {
+        query: &query,
+        locale,
+        history: &history,
+        flags: &screening.flags,
+    };
+    let mut upstream = match send_upstream(url, &body, None, &timeouts, deadline, &mut guard).await {
+        Ok(response) => response,
+        Err(e) => {
+            refund_quota(&state, ticket);
+            return Err(e);
+        }
+    };
 
-                    let data = data.trim();
-                    if data.is_empty() || data == "[DONE]" {
+    let mut parser = SseParser::new();
+    let mut response_text = String::new();
+    let mut sources: Vec<SearchResult> = Vec::new();
+    let mut first_token = false;
+    let mut done = None;
+
+    loop {
+        match next_chunk(&mut upstream, first_token, deadline, &timeouts).await {
+            Some(Ok(Some(bytes))) => {
+                for event in parser.feed(&bytes) {
+                    if is_done_sentinel(&event) {
                         continue;
                     }
 
-                    let Ok(value) = serde_json::from_str::<serde_json::Value>(data) else {
+                    let Ok(value) = serde_json::from_str::<serde_json::Value>(&event.data) else {
                         continue;
                     };
+                    recorder.observe(&value);
 
-                    let event_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                    match event_type {
+                    // The RAG service puts the kind in `type`; a named event works too.
+                    match event_type(&value).unwrap_or(event.event.as_str()) {
                         "sources" => {
                             if let Some(arr) = value.get("sources").and_then(|v| v.as_array()) {
-                                source_count = arr.len();
+                                sources = arr.iter().filter_map(|item| source_from(item, locale)).collect();
                             }
                         }
                         "content" => {
+                            first_token = true;
                             if let Some(content) = value.get("content").and_then(|v| v.as_str()) {
                                 response_text.push_str(content);
                             }
                         }
                         "done" => {
-                            done = true;
+                            done = Some(done_fields(&value));
                         }
                         _ => {}
                     }
                 }
 
-                if done {
+                if done.is_some() {
                     break;
                 }
             }
-            Ok(None) => break,
-            Err(e) => {
-                return Err((
-                    StatusCode::BAD_GATEWAY,
-                    Json(ErrorResponse {
-                        error: "Failed to read RAG stream".to_string(),
-                        details: Some(e.to_string()),
-                    }),
-                ));
+            Some(Ok(None)) => break,
+            Some(Err(e)) => {
+                guard.finish(StreamOutcome::Failed);
+                return Err(error_response(StatusCode::BAD_GATEWAY, "Failed to read RAG stream", Some(e.to_string())));
+            }
+            None => {
+                guard.finish(StreamOutcome::TimedOut);
+                return Err(error_response(StatusCode::GATEWAY_TIMEOUT, "RAG service timed out", None));
             }
         }
     }
 
+    guard.finish(StreamOutcome::Completed);
+    recorder.finish();
+
     Ok((
         StatusCode::OK,
         Json(ChatSimpleResponse {
             response: response_text,
-            has_context: source_count > 0,
-            source_count,
+            has_context: !sources.is_empty(),
+            source_count: sources.len(),
+            sources,
+            done,
+            quota,
+            thread_id: turn.as_ref().map(|(thread_id, _)| thread_id.clone()),
+            message_id: turn.map(|(_, message_id)| message_id),
         }),
     ))
 }
 
+/// POST /api/chat/quota
+/// Today's allowance for the caller; `null` when quotas are turned off.
+async fn quota_status(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<QuotaRequest>,
+) -> Result<Json<Option<QuotaStatus>>, (StatusCode, Json<ErrorResponse>)> {
+    let config = QuotaConfig::from_env();
+    if !config.enabled {
+        return Ok(Json(None));
+    }
+
+    let reader = chat_reader(&headers, payload.user_id);
+    let subject = quota_subject(reader.as_deref(), &headers, peer);
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        chat_quota::status(&mut conn, &subject, &config).map_err(|e| format!("DB query error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(status) => Ok(Json(Some(status))),
+        Err(e) => {
+            tracing::error!("quota_status error: {}", e);
+            Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to check chat quota", None))
+        }
+    }
+}
+
 pub fn router() -> Router<Arc<AppState>> {
-    Router::new().route("/", post(chat)).route("/simple", post(chat_simple))
+    Router::new()
+        .route("/", post(chat))
+        .route("/simple", post(chat_simple))
+        .route("/quota", post(quota_status))
+        .nest("/threads", super::chat_threads::router())
+        .nest("/feedback", super::chat_feedback::router())
+        .nest("/ws", super::chat_ws::router())
 }
 
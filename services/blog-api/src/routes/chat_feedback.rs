@@ -0,0 +1,120 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::auth::verify_internal_api_key;
+use crate::services::feedback::{self, FeedbackError, Rating, MAX_COMMENT_CHARS, RATING_DOWN, REASONS};
+use crate::services::AppState;
+
+#[derive(Deserialize)]
+pub struct FeedbackRequest {
+    pub user_id: String,
+    /// The `messageId` announced in the chat stream's `thread` event.
+    #[serde(alias = "messageId")]
+    pub message_id: String,
+    /// `"up"` or `"down"`.
+    pub rating: String,
+    /// One of [`REASONS`]; only kept for "down" ratings.
+    pub reason: Option<String>,
+    pub comment: Option<String>,
+    pub locale: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct FeedbackResponse {
+    pub success: bool,
+    pub message: String,
+    pub feedback_id: Option<String>,
+}
+
+impl FeedbackResponse {
+    fn error(message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+            feedback_id: None,
+        }
+    }
+}
+
+/// POST /api/chat/feedback
+async fn submit_feedback(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<FeedbackRequest>,
+) -> (StatusCode, Json<FeedbackResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(FeedbackResponse::error("Unauthorized")));
+    }
+
+    let Some(rating) = feedback::parse_rating(&payload.rating) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(FeedbackResponse::error("rating must be \"up\" or \"down\"")),
+        );
+    };
+
+    let reason = payload.reason.filter(|_| rating == RATING_DOWN);
+    if let Some(reason) = &reason {
+        if !REASONS.contains(&reason.as_str()) {
+            return (StatusCode::BAD_REQUEST, Json(FeedbackResponse::error("Unknown reason")));
+        }
+    }
+
+    let comment = payload
+        .comment
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty());
+    if comment.as_ref().is_some_and(|c| c.chars().count() > MAX_COMMENT_CHARS) {
+        return (StatusCode::BAD_REQUEST, Json(FeedbackResponse::error("Comment is too long")));
+    }
+
+    let locale = match payload.locale.as_deref() {
+        Some("en") => "en",
+        _ => "ko",
+    };
+
+    let pool = state.db.clone();
+    let rating = Rating {
+        rating,
+        reason,
+        comment,
+        locale: locale.to_string(),
+    };
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| FeedbackError::Connection(e.to_string()))?;
+        feedback::rate(&mut conn, &payload.user_id, &payload.message_id, rating)
+    })
+    .await
+    .unwrap_or_else(|e| Err(FeedbackError::Connection(format!("Task error: {}", e))));
+
+    match result {
+        Ok(id) => (
+            StatusCode::OK,
+            Json(FeedbackResponse {
+                success: true,
+                message: "Thanks for the feedback".to_string(),
+                feedback_id: Some(id),
+            }),
+        ),
+        Err(FeedbackError::NotFound) => (StatusCode::NOT_FOUND, Json(FeedbackResponse::error("Message not found"))),
+        Err(e) => {
+            tracing::error!("submit_feedback error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(FeedbackResponse::error("Failed to save feedback")),
+            )
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/", post(submit_feedback))
+}
@@ -0,0 +1,376 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json,
+    Router,
+};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Nullable, Text, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::auth::verify_internal_api_key;
+use crate::models::Message;
+use crate::schema::{messages, threads};
+use crate::services::threads::{owned_chat_thread, ThreadError, ADMIN_DM_THREAD_TITLE, MAX_TITLE_CHARS};
+use crate::services::AppState;
+
+const PREVIEW_CHARS: usize = 120;
+
+#[derive(Deserialize)]
+pub struct ListThreadsRequest {
+    pub user_id: String,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct ThreadMessagesRequest {
+    pub user_id: String,
+    pub thread_id: String,
+    /// Page 1 is the most recent messages; each page is returned oldest first.
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct RenameThreadRequest {
+    pub user_id: String,
+    pub thread_id: String,
+    pub title: String,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteThreadRequest {
+    pub user_id: String,
+    pub thread_id: String,
+}
+
+#[derive(QueryableByName)]
+struct ThreadRow {
+    #[diesel(sql_type = Text)]
+    id: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    title: Option<String>,
+    #[diesel(sql_type = Timestamp)]
+    created_at: NaiveDateTime,
+    #[diesel(sql_type = Timestamp)]
+    updated_at: NaiveDateTime,
+    #[diesel(sql_type = BigInt)]
+    message_count: i64,
+    #[diesel(sql_type = Nullable<Text>)]
+    last_role: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    last_preview: Option<String>,
+    #[diesel(sql_type = Nullable<Timestamp>)]
+    last_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct LastMessage {
+    pub role: String,
+    pub preview: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct ThreadSummary {
+    pub id: String,
+    pub title: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub message_count: i64,
+    pub last_message: Option<LastMessage>,
+}
+
+#[derive(Serialize)]
+pub struct ListThreadsResponse {
+    pub threads: Vec<ThreadSummary>,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[derive(Serialize)]
+pub struct ThreadMessagesResponse {
+    pub thread_id: String,
+    pub title: Option<String>,
+    pub messages: Vec<Message>,
+    pub page: i64,
+    pub per_page: i64,
+    pub has_more: bool,
+}
+
+#[derive(Serialize)]
+pub struct ThreadActionResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+impl ThreadActionResponse {
+    fn error(message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+        }
+    }
+}
+
+fn preview(content: &str) -> String {
+    let text = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= PREVIEW_CHARS {
+        return text;
+    }
+    let mut cut: String = text.chars().take(PREVIEW_CHARS - 1).collect();
+    cut.push('…');
+    cut
+}
+
+fn paging(page: Option<i64>, per_page: Option<i64>, default: i64) -> (i64, i64) {
+    (page.unwrap_or(1).max(1), per_page.unwrap_or(default).clamp(1, 100))
+}
+
+/// POST /api/chat/threads/list
+async fn list_threads(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ListThreadsRequest>,
+) -> (StatusCode, Json<Option<ListThreadsResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    let (page, per_page) = paging(payload.page, payload.per_page, 20);
+    let pool = state.db.clone();
+    let user_id = payload.user_id;
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let sql = r#"
+            SELECT
+                t.id::text AS id,
+                t.title::text AS title,
+                t."createdAt" AS created_at,
+                t."updatedAt" AS updated_at,
+                (SELECT COUNT(*) FROM "Message" c WHERE c."threadId" = t.id)::bigint AS message_count,
+                m.role::text AS last_role,
+                m.content::text AS last_preview,
+                m."createdAt" AS last_at
+            FROM "Thread" t
+            LEFT JOIN LATERAL (
+                SELECT role, content, "createdAt"
+                FROM "Message"
+                WHERE "threadId" = t.id
+                ORDER BY "createdAt" DESC
+                LIMIT 1
+            ) m ON true
+            WHERE t."userId" = $1
+              AND t.title IS DISTINCT FROM $2
+            ORDER BY t."updatedAt" DESC
+            LIMIT $3 OFFSET $4
+        "#;
+
+        let rows: Vec<ThreadRow> = sql_query(sql)
+            .bind::<Text, _>(&user_id)
+            .bind::<Text, _>(ADMIN_DM_THREAD_TITLE)
+            .bind::<BigInt, _>(per_page)
+            .bind::<BigInt, _>((page - 1) * per_page)
+            .load(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let threads = rows
+            .into_iter()
+            .map(|row| ThreadSummary {
+                last_message: match (row.last_role, row.last_preview, row.last_at) {
+                    (Some(role), Some(content), Some(created_at)) => Some(LastMessage {
+                        role,
+                        preview: preview(&content),
+                        created_at,
+                    }),
+                    _ => None,
+                },
+                id: row.id,
+                title: row.title,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+                message_count: row.message_count,
+            })
+            .collect();
+
+        Ok::<_, String>(ListThreadsResponse {
+            threads,
+            page,
+            per_page,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(response) => (StatusCode::OK, Json(Some(response))),
+        Err(e) => {
+            tracing::error!("list_threads error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// POST /api/chat/threads/messages
+async fn thread_messages(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ThreadMessagesRequest>,
+) -> (StatusCode, Json<Option<ThreadMessagesResponse>>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(None));
+    }
+
+    let (page, per_page) = paging(payload.page, payload.per_page, 50);
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| ThreadError::Connection(e.to_string()))?;
+        let thread_id = owned_chat_thread(&mut conn, &payload.user_id, &payload.thread_id)?;
+
+        let title: Option<String> = threads::table
+            .filter(threads::id.eq(&thread_id))
+            .select(threads::title)
+            .first(&mut conn)?;
+
+        // One extra row tells us whether an older page exists.
+        let mut rows: Vec<Message> = messages::table
+            .filter(messages::thread_id.eq(&thread_id))
+            .order((messages::created_at.desc(), messages::id.desc()))
+            .limit(per_page + 1)
+            .offset((page - 1) * per_page)
+            .select(Message::as_select())
+            .load(&mut conn)?;
+
+        let has_more = rows.len() as i64 > per_page;
+        rows.truncate(per_page as usize);
+        rows.reverse();
+
+        Ok::<_, ThreadError>(ThreadMessagesResponse {
+            thread_id,
+            title,
+            messages: rows,
+            page,
+            per_page,
+            has_more,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(ThreadError::Connection(format!("Task error: {}", e))));
+
+    match result {
+        Ok(response) => (StatusCode::OK, Json(Some(response))),
+        Err(ThreadError::NotFound) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(e) => {
+            tracing::error!("thread_messages error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// POST /api/chat/threads/rename
+async fn rename_thread(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<RenameThreadRequest>,
+) -> (StatusCode, Json<ThreadActionResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(ThreadActionResponse::error("Unauthorized")));
+    }
+
+    let title = payload.title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() || title.chars().count() > MAX_TITLE_CHARS || title == ADMIN_DM_THREAD_TITLE {
+        return (StatusCode::BAD_REQUEST, Json(ThreadActionResponse::error("Invalid title")));
+    }
+
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| ThreadError::Connection(e.to_string()))?;
+        let thread_id = owned_chat_thread(&mut conn, &payload.user_id, &payload.thread_id)?;
+
+        diesel::update(threads::table.filter(threads::id.eq(&thread_id)))
+            .set(threads::title.eq(Some(title)))
+            .execute(&mut conn)?;
+
+        Ok::<_, ThreadError>(())
+    })
+    .await
+    .unwrap_or_else(|e| Err(ThreadError::Connection(format!("Task error: {}", e))));
+
+    match result {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(ThreadActionResponse {
+                success: true,
+                message: "Thread renamed".to_string(),
+            }),
+        ),
+        Err(ThreadError::NotFound) => (StatusCode::NOT_FOUND, Json(ThreadActionResponse::error("Thread not found"))),
+        Err(e) => {
+            tracing::error!("rename_thread error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ThreadActionResponse::error("Failed to rename thread")),
+            )
+        }
+    }
+}
+
+/// POST /api/chat/threads/delete
+/// Messages are removed with the thread (ON DELETE CASCADE).
+async fn delete_thread(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<DeleteThreadRequest>,
+) -> (StatusCode, Json<ThreadActionResponse>) {
+    if verify_internal_api_key(&headers).is_err() {
+        return (StatusCode::UNAUTHORIZED, Json(ThreadActionResponse::error("Unauthorized")));
+    }
+
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| ThreadError::Connection(e.to_string()))?;
+        let thread_id = owned_chat_thread(&mut conn, &payload.user_id, &payload.thread_id)?;
+
+        diesel::delete(threads::table.filter(threads::id.eq(&thread_id))).execute(&mut conn)?;
+
+        Ok::<_, ThreadError>(())
+    })
+    .await
+    .unwrap_or_else(|e| Err(ThreadError::Connection(format!("Task error: {}", e))));
+
+    match result {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(ThreadActionResponse {
+                success: true,
+                message: "Thread deleted".to_string(),
+            }),
+        ),
+        Err(ThreadError::NotFound) => (StatusCode::NOT_FOUND, Json(ThreadActionResponse::error("Thread not found"))),
+        Err(e) => {
+            tracing::error!("delete_thread error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ThreadActionResponse::error("Failed to delete thread")),
+            )
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/list", post(list_threads))
+        .route("/messages", post(thread_messages))
+        .route("/rename", post(rename_thread))
+        .route("/delete", post(delete_thread))
+}
@@ -0,0 +1,262 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    routing::get,
+    Json,
+    Router,
+};
+use futures::stream::{BoxStream, StreamExt};
+use futures::SinkExt;
+use serde::Deserialize;
+use std::{net::SocketAddr, sync::Arc};
+
+use super::chat::{self, AnswerEvent, ChatRequest};
+use crate::services::chat_history::{self, HistoryConfig, HistoryTurn};
+use crate::services::chat_quota::Subject;
+use crate::services::chat_stream::StreamTimeouts;
+use crate::services::threads::{ROLE_ASSISTANT, ROLE_USER};
+use crate::services::AppState;
+
+/// Client frames are small JSON objects; anything bigger is refused.
+const MAX_FRAME_BYTES: usize = 64 * 1024;
+
+#[derive(Deserialize)]
+pub struct ConnectParams {
+    /// Signed-in reader; trusted only with the internal API key.
+    #[serde(alias = "userId")]
+    pub user_id: Option<String>,
+}
+
+/// What the client sends over the socket.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientFrame {
+    /// A question, with the same fields as a `POST /api/chat` body. Without
+    /// `thread_id`/`history` it follows up on the socket's conversation.
+    Ask(ChatRequest),
+    /// Stops the answer being generated.
+    Cancel,
+}
+
+/// One socket's conversation.
+struct Session {
+    state: Arc<AppState>,
+    reader: Option<String>,
+    subject: Subject,
+    /// Locale and thread of the last question, kept for follow-ups.
+    locale: Option<String>,
+    thread_id: Option<String>,
+    /// Earlier turns, for anonymous follow-ups.
+    history: Vec<HistoryTurn>,
+    question: String,
+    answer: String,
+}
+
+impl Session {
+    /// Fills in the conversation so far for a follow-up.
+    fn prepare(&mut self, mut ask: ChatRequest) -> ChatRequest {
+        ask.user_id = None;
+        if ask.locale.is_none() {
+            ask.locale = self.locale.clone();
+        }
+        self.locale = ask.locale.clone();
+
+        if self.reader.is_some() {
+            if ask.thread_id.is_none() {
+                ask.thread_id = self.thread_id.clone();
+            }
+        } else if ask.history.is_none() && !self.history.is_empty() {
+            ask.history = serde_json::to_value(&self.history).ok();
+        }
+
+        self.question = ask.query.clone().unwrap_or_default().trim().to_string();
+        self.answer.clear();
+        ask
+    }
+
+    /// Notes what the conversation needs from an outgoing event.
+    fn observe(&mut self, value: &serde_json::Value) {
+        match chat::event_type(value) {
+            Some("thread") => {
+                if let Some(id) = value.get("threadId").and_then(|v| v.as_str()) {
+                    self.thread_id = Some(id.to_string());
+                }
+            }
+            Some("content") => {
+                if let Some(content) = value.get("content").and_then(|v| v.as_str()) {
+                    self.answer.push_str(content);
+                }
+            }
+            Some("done") => {
+                let turns = [
+                    (ROLE_USER, std::mem::take(&mut self.question)),
+                    (ROLE_ASSISTANT, std::mem::take(&mut self.answer)),
+                ];
+                self.history.extend(turns.into_iter().map(|(role, content)| HistoryTurn {
+                    role: role.to_string(),
+                    content,
+                }));
+                self.history = chat_history::trim(std::mem::take(&mut self.history), &HistoryConfig::from_env());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Error frame for a question that was refused before answering started.
+fn refusal(status: StatusCode, error: chat::ErrorResponse) -> serde_json::Value {
+    let code = match status.as_u16() {
+        400 => "invalid_request",
+        402 => "insufficient_ink",
+        404 => "not_found",
+        422 => "declined",
+        429 => "quota_exceeded",
+        502 | 503 => "upstream",
+        504 => "timeout",
+        _ => "internal",
+    };
+
+    let mut frame = serde_json::json!({
+        "type": "error",
+        "code": code,
+        "message": error.error,
+    });
+    if let Some(details) = error.details {
+        frame["details"] = details.into();
+    }
+    if let Some(quota) = error.quota {
+        frame["quota"] = serde_json::to_value(quota).unwrap_or_default();
+    }
+    frame
+}
+
+fn invalid(message: &str) -> serde_json::Value {
+    serde_json::json!({ "type": "error", "code": "invalid_request", "message": message })
+}
+
+fn text(value: &serde_json::Value) -> Message {
+    Message::Text(value.to_string())
+}
+
+/// Acts on one client frame; returns the frame to answer with, if any.
+async fn handle(
+    session: &mut Session,
+    answer: &mut Option<BoxStream<'static, AnswerEvent>>,
+    text: &str,
+) -> Option<serde_json::Value> {
+    match serde_json::from_str::<ClientFrame>(text) {
+        Ok(ClientFrame::Ask(_)) if answer.is_some() => Some(serde_json::json!({
+            "type": "error",
+            "code": "busy",
+            "message": "An answer is still streaming; cancel it first",
+        })),
+        Ok(ClientFrame::Ask(ask)) => {
+            let ask = session.prepare(ask);
+            let reader = session.reader.clone();
+            match chat::answer(&session.state, reader, session.subject.clone(), None, ask).await {
+                Ok(events) => {
+                    *answer = Some(events.boxed());
+                    None
+                }
+                Err((status, Json(error))) => Some(refusal(status, error)),
+            }
+        }
+        Ok(ClientFrame::Cancel) => answer
+            .take()
+            .map(|_| serde_json::json!({ "type": "done", "cancelled": true })),
+        Err(e) => Some(invalid(&format!("Unrecognized frame: {}", e))),
+    }
+}
+
+/// Waits for the next event of the answer in flight; never resolves while
+/// there is none.
+async fn next_event(answer: &mut Option<BoxStream<'static, AnswerEvent>>) -> Option<AnswerEvent> {
+    match answer {
+        Some(events) => events.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// GET /api/chat/ws
+async fn chat_ws(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(params): Query<ConnectParams>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let reader = chat::chat_reader(&headers, params.user_id);
+    let subject = chat::quota_subject(reader.as_deref(), &headers, peer);
+
+    let session = Session {
+        state,
+        reader,
+        subject,
+        locale: None,
+        thread_id: None,
+        history: Vec::new(),
+        question: String::new(),
+        answer: String::new(),
+    };
+
+    ws.max_message_size(MAX_FRAME_BYTES)
+        .on_upgrade(move |socket| run(socket, session))
+}
+
+/// Serves one socket. One answer streams at a time; dropping it (on
+/// `cancel` or disconnect) closes the upstream request like a dropped SSE
+/// response does.
+async fn run(socket: WebSocket, mut session: Session) {
+    let (mut outgoing, mut incoming) = socket.split();
+    let mut answer: Option<BoxStream<'static, AnswerEvent>> = None;
+    let mut heartbeat = tokio::time::interval(StreamTimeouts::from_env().heartbeat);
+    heartbeat.tick().await;
+
+    loop {
+        let reply = tokio::select! {
+            message = incoming.next() => match message {
+                Some(Ok(Message::Text(text))) => handle(&mut session, &mut answer, &text).await,
+                Some(Ok(Message::Binary(_))) => Some(invalid("Frames must be JSON text")),
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => None,
+                Some(Err(e)) => {
+                    tracing::debug!("chat socket error: {}", e);
+                    break;
+                }
+            },
+            event = next_event(&mut answer) => match event {
+                Some(event) => {
+                    let Some(value) = event.value() else {
+                        continue;
+                    };
+                    session.observe(&value);
+                    Some(value)
+                }
+                None => {
+                    answer = None;
+                    None
+                }
+            },
+            _ = heartbeat.tick() => {
+                if outgoing.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+                None
+            }
+        };
+
+        if let Some(frame) = reply {
+            if outgoing.send(text(&frame)).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/", get(chat_ws))
+}
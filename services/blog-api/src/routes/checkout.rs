@@ -125,6 +125,7 @@ async fn create_session(
         success_url,
         cancel_url,
         is_subscription: payload.is_subscription,
+        locale: locale.to_string(),
     };
 
     match stripe::create_checkout_session(params).await {
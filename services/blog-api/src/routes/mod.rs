@@ -1,11 +1,20 @@
 pub mod auth;
 pub mod newsletter;
+pub mod newsletter_archive;
 pub mod checkout;
 pub mod chat;
+pub mod chat_threads;
+pub mod chat_feedback;
+pub mod chat_ws;
 pub mod search;
 pub mod webhook;
 pub mod shop;
 pub mod marginalia;
 pub mod admin;
+pub mod admin_newsletter;
+pub mod admin_email;
+pub mod admin_outbox;
+pub mod admin_chat;
 pub mod admin_dm;
+pub mod admin_inbox;
 pub mod onboarding;
@@ -1,19 +1,26 @@
 use axum::{
-    extract::State,
-    http::{HeaderMap, StatusCode},
-    routing::post,
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
     Json,
     Router,
 };
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use std::sync::Arc;
 
 use crate::auth::verify_internal_api_key;
 use crate::models::{NewsletterSubscription, NewNewsletterSubscription, NewsletterStatus};
 use crate::schema::{newsletter_subscriptions, users};
-use crate::services::resend::{send_email, EmailParams};
+use crate::services::mailer::OutgoingEmail;
+use crate::services::newsletter::{
+    base_url, find_subscription_by_token, generate_token, newsletter_from, newsletter_locale,
+    send_newsletter_email, sha256_hash, unsubscribe_url, FREQUENCY_EVERY_POST, FREQUENCY_WEEKLY_DIGEST, PAUSE_DAYS, TOPICS,
+};
+use crate::services::outbox;
+use crate::services::templates;
+use crate::services::tracking::{self, TrackingConfig};
 use crate::services::AppState;
 
 #[derive(Deserialize)]
@@ -26,6 +33,7 @@ pub struct SubscribeRequest {
 pub struct NewsletterDirectSubscribeRequest {
     pub user_id: String,
     pub email: String,
+    pub locale: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -40,68 +48,128 @@ pub struct UnsubscribeRequest {
 }
 
 #[derive(Deserialize)]
-pub struct NewsletterStatusRequest {
-    pub email: String,
+pub struct TrackingPreferenceRequest {
+    pub token: String,
+    pub enabled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct PreferencesRequest {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePreferencesRequest {
+    pub token: String,
+    pub topics: Option<Vec<String>>,
+    /// `every_post` or `weekly_digest`.
+    pub frequency: Option<String>,
+    pub locale: Option<String>,
+    /// Pause for 30, 60 or 90 days.
+    pub pause_days: Option<i64>,
+    /// Clears an active pause.
+    pub resume: Option<bool>,
+    pub tracking: Option<bool>,
 }
 
 #[derive(Serialize)]
-pub struct NewsletterStatusResponse {
+pub struct NewsletterPreferences {
     pub email: String,
     pub status: String,
-    pub confirmed_at: Option<String>,
-    pub unsubscribed_at: Option<String>,
+    pub locale: String,
+    pub topics: Vec<String>,
+    pub frequency: String,
+    pub paused_until: Option<String>,
+    pub tracking_enabled: bool,
+}
+
+impl From<NewsletterSubscription> for NewsletterPreferences {
+    fn from(sub: NewsletterSubscription) -> Self {
+        let now = chrono::Utc::now().naive_utc();
+
+        Self {
+            email: sub.email,
+            status: format!("{:?}", sub.status),
+            locale: sub.locale,
+            topics: sub.topics,
+            frequency: sub.frequency,
+            paused_until: sub.paused_until.filter(|until| *until > now).map(|dt| {
+                chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(dt, chrono::Utc).to_rfc3339()
+            }),
+            tracking_enabled: !sub.tracking_opt_out,
+        }
+    }
 }
 
 #[derive(Serialize)]
-pub struct NewsletterResponse {
+pub struct PreferencesResponse {
     pub success: bool,
-    pub status: String,
     pub message: String,
+    pub preferences: Option<NewsletterPreferences>,
 }
 
-fn normalize_email(email: &str) -> String {
-    email.trim().to_lowercase()
+impl PreferencesResponse {
+    fn error(message: &str) -> Self {
+        Self {
+            success: false,
+            message: message.to_string(),
+            preferences: None,
+        }
+    }
 }
 
-fn is_valid_email(email: &str) -> bool {
-    email.contains('@') && email.contains('.')
+#[derive(Deserialize)]
+pub struct TrackOpenQuery {
+    pub d: String,
+    pub s: String,
 }
 
-fn sha256_hash(value: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(value.as_bytes());
-    format!("{:x}", hasher.finalize())
+#[derive(Deserialize)]
+pub struct TrackClickQuery {
+    pub d: String,
+    pub u: String,
+    pub s: String,
 }
 
-fn generate_token() -> String {
-    use rand::RngCore;
+#[derive(Deserialize)]
+pub struct NewsletterStatusRequest {
+    pub email: String,
+}
 
-    let mut bytes = [0u8; 32];
-    rand::thread_rng().fill_bytes(&mut bytes);
-    hex::encode(bytes)
+#[derive(Serialize)]
+pub struct NewsletterStatusResponse {
+    pub email: String,
+    pub status: String,
+    pub confirmed_at: Option<String>,
+    pub unsubscribed_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct NewsletterResponse {
+    pub success: bool,
+    pub status: String,
+    pub message: String,
 }
 
-fn base_url() -> String {
-    std::env::var("NEXT_PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:7071".to_string())
+pub(crate) fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
 }
 
-fn newsletter_from() -> String {
-    std::env::var("NEWSLETTER_FROM")
-        .or_else(|_| std::env::var("AUTH_EMAIL_FROM"))
-        .unwrap_or_else(|_| "Archives <onboarding@resend.dev>".to_string())
+pub(crate) fn is_valid_email(email: &str) -> bool {
+    email.contains('@') && email.contains('.')
 }
 
 async fn subscribe(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<SubscribeRequest>,
 ) -> (StatusCode, Json<NewsletterResponse>) {
-    if std::env::var("RESEND_API_KEY").is_err() {
+    if !state.mailer.is_configured() {
         return (
             StatusCode::SERVICE_UNAVAILABLE,
             Json(NewsletterResponse {
                 success: false,
                 status: "ERROR".to_string(),
-                message: "Email delivery is not configured. Set RESEND_API_KEY.".to_string(),
+                message: "Email delivery is not configured".to_string(),
             }),
         );
     }
@@ -119,8 +187,7 @@ async fn subscribe(
         );
     }
 
-    let locale = payload.locale.unwrap_or_else(|| "ko".to_string());
-    let locale = if locale == "en" { "en" } else { "ko" };
+    let locale = newsletter_locale(payload.locale.as_deref().unwrap_or("ko"));
 
     let confirm_token = generate_token();
     let unsubscribe_token = generate_token();
@@ -130,53 +197,86 @@ async fn subscribe(
 
     let base_url = base_url();
     let confirm_url = format!("{}/{}/newsletter/confirm?token={}", base_url, locale, confirm_token);
-    let unsubscribe_url = format!(
-        "{}/{}/newsletter/unsubscribe?token={}",
-        base_url, locale, unsubscribe_token
-    );
+    let unsubscribe_url = unsubscribe_url(locale, &unsubscribe_token);
+
+    let template = templates::NewsletterConfirm {
+        confirm_url,
+        unsubscribe_url,
+    };
+    let message = match state.templates.render(&template, locale) {
+        Ok(content) => OutgoingEmail::new(newsletter_from(), email.clone(), content),
+        Err(e) => {
+            tracing::error!("Newsletter confirm render failed: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(NewsletterResponse {
+                    success: false,
+                    status: "ERROR".to_string(),
+                    message: "Failed to send confirmation email".to_string(),
+                }),
+            );
+        }
+    };
 
     let pool = state.db.clone();
-    let email_for_db = email.clone();
-    let confirm_hash_for_db = confirm_token_hash.clone();
-    let unsubscribe_hash_for_db = unsubscribe_token_hash.clone();
 
     let db_result = tokio::task::spawn_blocking(move || {
         let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
 
-        let existing: Option<NewsletterSubscription> = newsletter_subscriptions::table
-            .filter(newsletter_subscriptions::email.eq(&email_for_db))
-            .first(&mut conn)
-            .optional()
-            .map_err(|e| format!("DB query error: {}", e))?;
+        // The confirmation email is queued in the same transaction as the
+        // token it carries.
+        conn.transaction::<_, anyhow::Error, _>(|conn| {
+            let existing: Option<NewsletterSubscription> = newsletter_subscriptions::table
+                .filter(newsletter_subscriptions::email.eq(&email))
+                .first(conn)
+                .optional()?;
 
-        if let Some(ref sub) = existing {
-            if sub.status == NewsletterStatus::ACTIVE {
-                return Ok::<_, String>((
-                    StatusCode::OK,
-                    NewsletterResponse {
-                        success: true,
-                        status: "ACTIVE".to_string(),
-                        message: "Already subscribed".to_string(),
-                    },
-                    false,
-                ));
+            if let Some(ref sub) = existing {
+                if sub.status == NewsletterStatus::ACTIVE {
+                    return Ok((
+                        StatusCode::OK,
+                        NewsletterResponse {
+                            success: true,
+                            status: "ACTIVE".to_string(),
+                            message: "Already subscribed".to_string(),
+                        },
+                        false,
+                    ));
+                }
+
+                diesel::update(
+                    newsletter_subscriptions::table
+                        .filter(newsletter_subscriptions::email.eq(&email)),
+                )
+                .set((
+                    newsletter_subscriptions::status.eq(NewsletterStatus::PENDING),
+                    newsletter_subscriptions::confirm_token_hash.eq(Some(confirm_token_hash)),
+                    newsletter_subscriptions::unsubscribe_token_hash.eq(Some(unsubscribe_token_hash)),
+                    newsletter_subscriptions::confirmed_at.eq::<Option<chrono::NaiveDateTime>>(None),
+                    newsletter_subscriptions::unsubscribed_at.eq::<Option<chrono::NaiveDateTime>>(None),
+                    newsletter_subscriptions::locale.eq(locale),
+                ))
+                .execute(conn)?;
+            } else {
+                let new_sub = NewNewsletterSubscription {
+                    id: cuid2::create_id(),
+                    email: email.clone(),
+                    status: NewsletterStatus::PENDING,
+                    user_id: None,
+                    confirm_token_hash: Some(confirm_token_hash),
+                    unsubscribe_token_hash: Some(unsubscribe_token_hash),
+                    locale: locale.to_string(),
+                    source: "web".to_string(),
+                };
+
+                diesel::insert_into(newsletter_subscriptions::table)
+                    .values(&new_sub)
+                    .execute(conn)?;
             }
 
-            diesel::update(
-                newsletter_subscriptions::table
-                    .filter(newsletter_subscriptions::email.eq(&email_for_db)),
-            )
-            .set((
-                newsletter_subscriptions::status.eq(NewsletterStatus::PENDING),
-                newsletter_subscriptions::confirm_token_hash.eq(Some(confirm_hash_for_db)),
-                newsletter_subscriptions::unsubscribe_token_hash.eq(Some(unsubscribe_hash_for_db)),
-                newsletter_subscriptions::confirmed_at.eq::<Option<chrono::NaiveDateTime>>(None),
-                newsletter_subscriptions::unsubscribed_at.eq::<Option<chrono::NaiveDateTime>>(None),
-            ))
-            .execute(&mut conn)
-            .map_err(|e| format!("DB update error: {}", e))?;
+            outbox::enqueue(conn, outbox::KIND_NEWSLETTER_CONFIRM, &message)?;
 
-            return Ok((
+            Ok((
                 StatusCode::OK,
                 NewsletterResponse {
                     success: true,
@@ -184,83 +284,32 @@ async fn subscribe(
                     message: "Check your email to confirm".to_string(),
                 },
                 true,
-            ));
-        }
-
-        let new_sub = NewNewsletterSubscription {
-            id: cuid2::create_id(),
-            email: email_for_db,
-            status: NewsletterStatus::PENDING,
-            user_id: None,
-            confirm_token_hash: Some(confirm_hash_for_db),
-            unsubscribe_token_hash: Some(unsubscribe_hash_for_db),
-        };
-
-        diesel::insert_into(newsletter_subscriptions::table)
-            .values(&new_sub)
-            .execute(&mut conn)
-            .map_err(|e| format!("DB insert error: {}", e))?;
-
-        Ok::<_, String>((
-            StatusCode::OK,
-            NewsletterResponse {
-                success: true,
-                status: "PENDING".to_string(),
-                message: "Check your email to confirm".to_string(),
-            },
-            true,
-        ))
+            ))
+        })
+        .map_err(|e| format!("DB error: {}", e))
     })
     .await
     .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
 
-    let (status, response, should_send_email) = match db_result {
-        Ok(v) => v,
+    match db_result {
+        Ok((status, response, queued)) => {
+            if queued {
+                outbox::wake(&state);
+            }
+            (status, Json(response))
+        }
         Err(e) => {
             tracing::error!("Subscribe error: {}", e);
-            return (
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(NewsletterResponse {
                     success: false,
                     status: "ERROR".to_string(),
                     message: "Internal server error".to_string(),
                 }),
-            );
+            )
         }
-    };
-
-    if !should_send_email {
-        return (status, Json(response));
-    }
-
-    let html = format!(
-        "<p>Confirm your subscription to Archives.</p>\
-         <p><a href=\"{}\">Confirm subscription</a></p>\
-         <hr />\
-         <p><a href=\"{}\">Unsubscribe</a></p>",
-        confirm_url, unsubscribe_url
-    );
-
-    if let Err(e) = send_email(EmailParams {
-        from: newsletter_from(),
-        to: email,
-        subject: "Confirm your subscription".to_string(),
-        html,
-    })
-    .await
-    {
-        tracing::error!("Newsletter email send failed: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(NewsletterResponse {
-                success: false,
-                status: "ERROR".to_string(),
-                message: "Failed to send confirmation email".to_string(),
-            }),
-        );
     }
-
-    (status, Json(response))
 }
 
 async fn subscribe_direct(
@@ -292,6 +341,7 @@ async fn subscribe_direct(
     }
 
     let user_id = payload.user_id;
+    let locale = newsletter_locale(payload.locale.as_deref().unwrap_or("ko"));
     let pool = state.db.clone();
 
     let result = tokio::task::spawn_blocking(move || {
@@ -324,6 +374,8 @@ async fn subscribe_direct(
                     user_id: Some(user_id.clone()),
                     confirm_token_hash: None,
                     unsubscribe_token_hash: None,
+                    locale: locale.to_string(),
+                    source: "direct".to_string(),
                 };
 
                 diesel::insert_into(newsletter_subscriptions::table)
@@ -388,6 +440,10 @@ async fn confirm(
     }
 
     let token_hash = sha256_hash(&token);
+    // The raw unsubscribe token from the confirmation mail is not stored, so
+    // a fresh one is issued for the welcome mail's unsubscribe link.
+    let unsubscribe_token = generate_token();
+    let unsubscribe_token_hash = sha256_hash(&unsubscribe_token);
     let pool = state.db.clone();
 
     let result = tokio::task::spawn_blocking(move || {
@@ -410,6 +466,7 @@ async fn confirm(
                         status: "ERROR".to_string(),
                         message: "Invalid or expired token".to_string(),
                     },
+                    None,
                 ))
             }
         };
@@ -422,6 +479,7 @@ async fn confirm(
                 newsletter_subscriptions::confirmed_at.eq(Some(now)),
                 newsletter_subscriptions::unsubscribed_at.eq::<Option<chrono::NaiveDateTime>>(None),
                 newsletter_subscriptions::confirm_token_hash.eq::<Option<String>>(None),
+                newsletter_subscriptions::unsubscribe_token_hash.eq(Some(unsubscribe_token_hash)),
             ))
             .execute(&mut conn)
             .map_err(|e| format!("DB update error: {}", e))?;
@@ -437,13 +495,28 @@ async fn confirm(
                 status: "ACTIVE".to_string(),
                 message: "Subscription confirmed".to_string(),
             },
+            Some((sub.email, sub.locale)),
         ))
     })
     .await
     .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
 
     match result {
-        Ok((status, response)) => (status, Json(response)),
+        Ok((status, response, welcome)) => {
+            if let Some((email, locale)) = welcome {
+                let locale = newsletter_locale(&locale);
+                let template = templates::NewsletterWelcome {
+                    archive_url: format!("{}/{}", base_url(), locale),
+                    unsubscribe_url: unsubscribe_url(locale, &unsubscribe_token),
+                };
+
+                if let Err(e) = send_newsletter_email(&state, email, &template, locale).await {
+                    tracing::warn!("Newsletter welcome email send failed: {}", e);
+                }
+            }
+
+            (status, Json(response))
+        }
         Err(e) => {
             tracing::error!("Confirm error: {}", e);
             (
@@ -483,10 +556,7 @@ async fn unsubscribe(
         tokio::task::spawn_blocking(move || {
             let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
 
-            let subscription: Option<NewsletterSubscription> = newsletter_subscriptions::table
-                .filter(newsletter_subscriptions::unsubscribe_token_hash.eq(&token_hash))
-                .first(&mut conn)
-                .optional()
+            let subscription = find_subscription_by_token(&mut conn, &token_hash)
                 .map_err(|e| format!("DB query error: {}", e))?;
 
             let sub = match subscription {
@@ -499,6 +569,7 @@ async fn unsubscribe(
                             status: "ERROR".to_string(),
                             message: "Invalid token".to_string(),
                         },
+                        None,
                     ))
                 }
             };
@@ -518,6 +589,8 @@ async fn unsubscribe(
                 .set(users::newsletter_opt_in_at.eq::<Option<chrono::NaiveDateTime>>(None))
                 .execute(&mut conn);
 
+            let goodbye = (sub.status != NewsletterStatus::UNSUBSCRIBED).then_some((sub.email, sub.locale));
+
             Ok::<_, String>((
                 StatusCode::OK,
                 NewsletterResponse {
@@ -525,6 +598,7 @@ async fn unsubscribe(
                     status: "UNSUBSCRIBED".to_string(),
                     message: "Unsubscribed".to_string(),
                 },
+                goodbye,
             ))
         })
         .await
@@ -541,6 +615,8 @@ async fn unsubscribe(
                 .optional()
                 .map_err(|e| format!("DB query error: {}", e))?;
 
+            let mut goodbye = None;
+
             if let Some(sub) = existing {
                 if sub.status != NewsletterStatus::UNSUBSCRIBED {
                     let now = chrono::Utc::now().naive_utc();
@@ -557,6 +633,8 @@ async fn unsubscribe(
                     let _ = diesel::update(users::table.filter(users::email.eq(Some(sub.email.as_str()))))
                         .set(users::newsletter_opt_in_at.eq::<Option<chrono::NaiveDateTime>>(None))
                         .execute(&mut conn);
+
+                    goodbye = Some((sub.email, sub.locale));
                 }
             }
 
@@ -567,6 +645,7 @@ async fn unsubscribe(
                     status: "UNSUBSCRIBED".to_string(),
                     message: "Unsubscribed".to_string(),
                 },
+                goodbye,
             ))
         })
         .await
@@ -583,7 +662,20 @@ async fn unsubscribe(
     };
 
     match result {
-        Ok((status, response)) => (status, Json(response)),
+        Ok((status, response, goodbye)) => {
+            if let Some((email, locale)) = goodbye {
+                let locale = newsletter_locale(&locale);
+                let template = templates::NewsletterGoodbye {
+                    resubscribe_url: format!("{}/{}/newsletter", base_url(), locale),
+                };
+
+                if let Err(e) = send_newsletter_email(&state, email, &template, locale).await {
+                    tracing::warn!("Newsletter goodbye email send failed: {}", e);
+                }
+            }
+
+            (status, Json(response))
+        }
         Err(e) => {
             tracing::error!("Unsubscribe error: {}", e);
             (
@@ -663,6 +755,263 @@ async fn status(
     }
 }
 
+async fn tracking_preference(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<TrackingPreferenceRequest>,
+) -> (StatusCode, Json<NewsletterResponse>) {
+    let token_hash = sha256_hash(payload.token.trim());
+    let opt_out = !payload.enabled;
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let subscription = find_subscription_by_token(&mut conn, &token_hash)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let Some(sub) = subscription else {
+            return Ok(None);
+        };
+
+        diesel::update(newsletter_subscriptions::table.filter(newsletter_subscriptions::id.eq(&sub.id)))
+            .set((
+                newsletter_subscriptions::tracking_opt_out.eq(opt_out),
+                newsletter_subscriptions::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(&mut conn)
+            .map_err(|e| format!("DB update error: {}", e))?;
+
+        Ok::<_, String>(Some(format!("{:?}", sub.status)))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(Some(status)) => (
+            StatusCode::OK,
+            Json(NewsletterResponse {
+                success: true,
+                status,
+                message: if opt_out { "Tracking disabled" } else { "Tracking enabled" }.to_string(),
+            }),
+        ),
+        Ok(None) => (
+            StatusCode::BAD_REQUEST,
+            Json(NewsletterResponse {
+                success: false,
+                status: "ERROR".to_string(),
+                message: "Invalid token".to_string(),
+            }),
+        ),
+        Err(e) => {
+            tracing::error!("Tracking preference error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(NewsletterResponse {
+                    success: false,
+                    status: "ERROR".to_string(),
+                    message: "Internal server error".to_string(),
+                }),
+            )
+        }
+    }
+}
+
+async fn get_preferences(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<PreferencesRequest>,
+) -> (StatusCode, Json<PreferencesResponse>) {
+    let token_hash = sha256_hash(payload.token.trim());
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        find_subscription_by_token(&mut conn, &token_hash).map_err(|e| format!("DB query error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(Some(sub)) => (
+            StatusCode::OK,
+            Json(PreferencesResponse {
+                success: true,
+                message: "OK".to_string(),
+                preferences: Some(sub.into()),
+            }),
+        ),
+        Ok(None) => (StatusCode::BAD_REQUEST, Json(PreferencesResponse::error("Invalid token"))),
+        Err(e) => {
+            tracing::error!("Get preferences error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(PreferencesResponse::error("Internal server error")),
+            )
+        }
+    }
+}
+
+async fn update_preferences(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdatePreferencesRequest>,
+) -> (StatusCode, Json<PreferencesResponse>) {
+    let topics = match payload.topics {
+        Some(topics) => {
+            let mut selected: Vec<String> = Vec::new();
+            for topic in topics {
+                let topic = topic.trim().to_string();
+                if !TOPICS.contains(&topic.as_str()) {
+                    return (StatusCode::BAD_REQUEST, Json(PreferencesResponse::error("Invalid topic")));
+                }
+                if !selected.contains(&topic) {
+                    selected.push(topic);
+                }
+            }
+            if selected.is_empty() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(PreferencesResponse::error("Choose at least one topic, or unsubscribe")),
+                );
+            }
+            Some(selected)
+        }
+        None => None,
+    };
+
+    if let Some(frequency) = payload.frequency.as_deref() {
+        if frequency != FREQUENCY_EVERY_POST && frequency != FREQUENCY_WEEKLY_DIGEST {
+            return (StatusCode::BAD_REQUEST, Json(PreferencesResponse::error("Invalid frequency")));
+        }
+    }
+
+    if let Some(days) = payload.pause_days {
+        if !PAUSE_DAYS.contains(&days) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(PreferencesResponse::error("Pause must be 30, 60 or 90 days")),
+            );
+        }
+    }
+
+    let token_hash = sha256_hash(payload.token.trim());
+    let locale = payload.locale.as_deref().map(newsletter_locale);
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let subscription = find_subscription_by_token(&mut conn, &token_hash)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let Some(sub) = subscription else {
+            return Ok(None);
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        let paused_until = match (payload.pause_days, payload.resume) {
+            (Some(days), _) => Some(now + chrono::Duration::days(days)),
+            (None, Some(true)) => None,
+            (None, _) => sub.paused_until,
+        };
+
+        diesel::update(newsletter_subscriptions::table.filter(newsletter_subscriptions::id.eq(&sub.id)))
+            .set((
+                newsletter_subscriptions::topics.eq(topics.unwrap_or(sub.topics)),
+                newsletter_subscriptions::frequency.eq(payload.frequency.unwrap_or(sub.frequency)),
+                newsletter_subscriptions::locale.eq(locale.map(str::to_string).unwrap_or(sub.locale)),
+                newsletter_subscriptions::paused_until.eq(paused_until),
+                newsletter_subscriptions::tracking_opt_out
+                    .eq(payload.tracking.map(|t| !t).unwrap_or(sub.tracking_opt_out)),
+                newsletter_subscriptions::updated_at.eq(now),
+            ))
+            .get_result::<NewsletterSubscription>(&mut conn)
+            .map(Some)
+            .map_err(|e| format!("DB update error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(Some(sub)) => (
+            StatusCode::OK,
+            Json(PreferencesResponse {
+                success: true,
+                message: "Preferences updated".to_string(),
+                preferences: Some(sub.into()),
+            }),
+        ),
+        Ok(None) => (StatusCode::BAD_REQUEST, Json(PreferencesResponse::error("Invalid token"))),
+        Err(e) => {
+            tracing::error!("Update preferences error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(PreferencesResponse::error("Internal server error")),
+            )
+        }
+    }
+}
+
+async fn record_tracking_event(state: &Arc<AppState>, delivery_id: String, kind: &'static str, url: Option<String>) {
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        tracking::record_event(&mut conn, &delivery_id, kind, url.as_deref())
+            .map_err(|e| format!("DB insert error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    if let Err(e) = result {
+        tracing::warn!("Newsletter {} tracking error: {}", kind, e);
+    }
+}
+
+/// Open pixel. Always answers with the GIF so mail clients never show a
+/// broken image, even when the signature is bad or DNT is set.
+async fn track_open(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<TrackOpenQuery>,
+) -> Response {
+    let valid = TrackingConfig::from_env()
+        .is_some_and(|config| config.verify(&[tracking::EVENT_OPEN, &params.d], &params.s));
+
+    if valid && !tracking::do_not_track(&headers) {
+        record_tracking_event(&state, params.d, tracking::EVENT_OPEN, None).await;
+    }
+
+    (
+        [
+            (header::CONTENT_TYPE, "image/gif"),
+            (header::CACHE_CONTROL, "no-store, no-cache, must-revalidate, private"),
+        ],
+        tracking::PIXEL_GIF,
+    )
+        .into_response()
+}
+
+/// Signed click redirect. Unsigned or tampered URLs are rejected so this
+/// can't be used as an open redirect.
+async fn track_click(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<TrackClickQuery>,
+) -> Response {
+    let valid = TrackingConfig::from_env()
+        .is_some_and(|config| config.verify(&[tracking::EVENT_CLICK, &params.d, &params.u], &params.s));
+
+    if !valid || !(params.u.starts_with("https://") || params.u.starts_with("http://")) {
+        return (StatusCode::BAD_REQUEST, "Invalid link").into_response();
+    }
+
+    if !tracking::do_not_track(&headers) {
+        record_tracking_event(&state, params.d, tracking::EVENT_CLICK, Some(params.u.clone())).await;
+    }
+
+    Redirect::to(&params.u).into_response()
+}
+
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/subscribe", post(subscribe))
@@ -670,4 +1019,10 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/unsubscribe", post(unsubscribe))
         .route("/confirm", post(confirm))
         .route("/status", post(status))
+        .route("/tracking", post(tracking_preference))
+        .route("/preferences", post(get_preferences))
+        .route("/preferences/update", post(update_preferences))
+        .route("/t/open", get(track_open))
+        .route("/t/click", get(track_click))
+        .nest("/archive", super::newsletter_archive::router())
 }
@@ -0,0 +1,226 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::get,
+    Json,
+    Router,
+};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::auth::verify_internal_api_key;
+use crate::models::NewsletterIssue;
+use crate::schema::newsletter_issues;
+use crate::services::newsletter::{base_url, newsletter_locale, ISSUE_SENT};
+use crate::services::{membership, templates, tracking, AppState};
+
+const EXCERPT_CHARS: usize = 160;
+
+#[derive(Deserialize)]
+pub struct ArchiveListQuery {
+    pub locale: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct ArchiveIssueQuery {
+    /// Reader to check for a paid membership. Only trusted together with
+    /// the internal API key, so it has to come from the blog server.
+    pub user_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ArchiveItem {
+    pub id: String,
+    pub subject: String,
+    pub locale: String,
+    pub kind: String,
+    pub members_only: bool,
+    pub sent_at: Option<String>,
+    /// Empty for members-only issues.
+    pub excerpt: String,
+}
+
+#[derive(Serialize)]
+pub struct ArchiveListResponse {
+    pub items: Vec<ArchiveItem>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+#[derive(Serialize)]
+pub struct ArchiveIssueResponse {
+    pub id: String,
+    pub subject: String,
+    pub locale: String,
+    pub members_only: bool,
+    pub sent_at: Option<String>,
+    pub html: String,
+}
+
+fn format_timestamp(dt: Option<NaiveDateTime>) -> Option<String> {
+    dt.map(|dt| chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(dt, chrono::Utc).to_rfc3339())
+}
+
+fn excerpt(issue: &NewsletterIssue) -> String {
+    if issue.members_only {
+        return String::new();
+    }
+
+    let text = issue.text.clone().unwrap_or_default();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > EXCERPT_CHARS {
+        format!("{}…", text.chars().take(EXCERPT_CHARS).collect::<String>().trim_end())
+    } else {
+        text
+    }
+}
+
+async fn list_archive(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ArchiveListQuery>,
+) -> (StatusCode, Json<Option<ArchiveListResponse>>) {
+    let locale = newsletter_locale(params.locale.as_deref().unwrap_or("ko"));
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(20).clamp(1, 50);
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let sent = newsletter_issues::table
+            .filter(newsletter_issues::status.eq(ISSUE_SENT))
+            .filter(newsletter_issues::locale.eq(locale));
+
+        let total: i64 = sent
+            .count()
+            .get_result(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let issues: Vec<NewsletterIssue> = sent
+            .order(newsletter_issues::sent_at.desc())
+            .offset((page - 1) * per_page)
+            .limit(per_page)
+            .load(&mut conn)
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let items = issues
+            .iter()
+            .map(|issue| ArchiveItem {
+                id: issue.id.clone(),
+                subject: issue.subject.clone(),
+                locale: issue.locale.clone(),
+                kind: issue.kind.clone(),
+                members_only: issue.members_only,
+                sent_at: format_timestamp(issue.sent_at),
+                excerpt: excerpt(issue),
+            })
+            .collect();
+
+        Ok::<_, String>(ArchiveListResponse {
+            items,
+            page,
+            per_page,
+            total,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok(response) => (StatusCode::OK, Json(Some(response))),
+        Err(e) => {
+            tracing::error!("list_archive error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+async fn get_archive_issue(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(issue_id): Path<String>,
+    Query(params): Query<ArchiveIssueQuery>,
+) -> (StatusCode, Json<Option<ArchiveIssueResponse>>) {
+    let reader = if verify_internal_api_key(&headers).is_ok() {
+        params.user_id.filter(|id| !id.trim().is_empty())
+    } else {
+        None
+    };
+    let pool = state.db.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+        let issue: Option<NewsletterIssue> = newsletter_issues::table
+            .filter(newsletter_issues::id.eq(&issue_id))
+            .filter(newsletter_issues::status.eq(ISSUE_SENT))
+            .first(&mut conn)
+            .optional()
+            .map_err(|e| format!("DB query error: {}", e))?;
+
+        let Some(issue) = issue else {
+            return Ok((StatusCode::NOT_FOUND, None));
+        };
+
+        if issue.members_only {
+            let allowed = match &reader {
+                Some(user_id) => membership::is_paid_member(&mut conn, user_id)
+                    .map_err(|e| format!("DB query error: {}", e))?,
+                None => false,
+            };
+            if !allowed {
+                return Ok((StatusCode::FORBIDDEN, None));
+            }
+        }
+
+        Ok::<_, String>((StatusCode::OK, Some(issue)))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+    match result {
+        Ok((StatusCode::OK, Some(issue))) => {
+            let locale = newsletter_locale(&issue.locale);
+            let page = templates::NewsletterArchivePage {
+                subject: issue.subject.clone(),
+                body_html: tracking::strip_recipient_markup(&issue.html, &tracking::own_origins()),
+                subscribe_url: format!("{}/{}/newsletter", base_url(), locale),
+            };
+            let html = match state.templates.render(&page, locale) {
+                Ok(content) => content.html,
+                Err(e) => {
+                    tracing::error!("get_archive_issue render error: {}", e);
+                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(None));
+                }
+            };
+
+            (
+                StatusCode::OK,
+                Json(Some(ArchiveIssueResponse {
+                    html,
+                    id: issue.id,
+                    subject: issue.subject,
+                    locale: issue.locale,
+                    members_only: issue.members_only,
+                    sent_at: format_timestamp(issue.sent_at),
+                })),
+            )
+        }
+        Ok((status, _)) => (status, Json(None)),
+        Err(e) => {
+            tracing::error!("get_archive_issue error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_archive))
+        .route("/:id", get(get_archive_issue))
+}
@@ -20,6 +20,7 @@ pub struct CompleteOnboardingRequest {
     pub email: String,
     pub accept_terms: bool,
     pub newsletter_opt_in: bool,
+    pub locale: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -57,6 +58,7 @@ async fn complete_onboarding(
     let user_id = payload.user_id;
     let email = payload.email.trim().to_lowercase();
     let newsletter_opt_in = payload.newsletter_opt_in;
+    let locale = if payload.locale.as_deref() == Some("en") { "en" } else { "ko" };
 
     let result = tokio::task::spawn_blocking(move || {
         let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
@@ -96,6 +98,8 @@ async fn complete_onboarding(
                         user_id: Some(user_id.clone()),
                         confirm_token_hash: None,
                         unsubscribe_token_hash: None,
+                        locale: locale.to_string(),
+                        source: "onboarding".to_string(),
                     };
 
                     diesel::insert_into(newsletter_subscriptions::table)
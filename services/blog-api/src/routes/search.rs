@@ -26,6 +26,8 @@ pub struct SearchResult {
     pub content_type: String,
     pub similarity: f32,
     pub excerpt: String,
+    /// Missing from chat sources, which are always in the request locale.
+    #[serde(default)]
     pub locale: String,
 }
 
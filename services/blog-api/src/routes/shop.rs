@@ -12,7 +12,8 @@ use std::sync::Arc;
 use crate::auth::verify_internal_api_key;
 use crate::models::NewOrder;
 use crate::schema::{orders, products, users};
-use crate::services::AppState;
+use crate::services::receipts::{self, Payment};
+use crate::services::{outbox, AppState};
 
 #[derive(Serialize)]
 pub struct ProductDto {
@@ -40,6 +41,8 @@ pub struct InkPointsRequest {
 pub struct BuyProductRequest {
     pub user_id: String,
     pub product_id: String,
+    /// Language of the receipt email.
+    pub locale: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -174,8 +177,10 @@ async fn buy_product(
     }
 
     let pool = state.db.clone();
+    let engine = state.templates.clone();
     let user_id = payload.user_id;
     let product_id = payload.product_id;
+    let locale = payload.locale.unwrap_or_else(|| "ko".to_string());
 
     let result = tokio::task::spawn_blocking(move || {
         let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
@@ -223,6 +228,8 @@ async fn buy_product(
                 .values(&order)
                 .execute(conn)?;
 
+            receipts::queue_order_email(conn, &engine, &order.id, Payment::Points(point_price), &locale)?;
+
             Ok::<_, anyhow::Error>((product_name, remaining))
         })
         .map_err(|e| e.to_string())
@@ -231,14 +238,17 @@ async fn buy_product(
     .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
 
     match result {
-        Ok((product_name, remaining)) => (
-            StatusCode::OK,
-            Json(PurchaseResult {
-                success: true,
-                message: format!("\"{}\" 구매 완료! (Purchase successful)", product_name),
-                remaining_points: Some(remaining),
-            }),
-        ),
+        Ok((product_name, remaining)) => {
+            outbox::wake(&state);
+            (
+                StatusCode::OK,
+                Json(PurchaseResult {
+                    success: true,
+                    message: format!("\"{}\" 구매 완료! (Purchase successful)", product_name),
+                    remaining_points: Some(remaining),
+                }),
+            )
+        }
         Err(e) => (
             StatusCode::BAD_REQUEST,
             Json(PurchaseResult {
@@ -12,7 +12,8 @@ use std::sync::Arc;
 
 use crate::models::{NewOrder, Role};
 use crate::schema::{orders, products, users};
-use crate::services::AppState;
+use crate::services::receipts::{self, Payment};
+use crate::services::{outbox, AppState};
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -88,6 +89,8 @@ struct StripeEventData {
 #[derive(Debug, serde::Deserialize)]
 struct CheckoutSession {
     metadata: Option<SessionMetadata>,
+    amount_total: Option<i64>,
+    currency: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -96,6 +99,7 @@ struct SessionMetadata {
     user_id: Option<String>,
     #[serde(rename = "productId")]
     product_id: Option<String>,
+    locale: Option<String>,
 }
 
 async fn stripe_webhook(
@@ -170,12 +174,22 @@ async fn stripe_webhook(
             }
         };
 
+        let locale = metadata.locale.clone().unwrap_or_else(|| "ko".to_string());
+        let payment = match (event.data.object.amount_total, &event.data.object.currency) {
+            (Some(amount), Some(currency)) => Some(Payment::Cash {
+                amount,
+                currency: currency.clone(),
+            }),
+            _ => None,
+        };
+
         let pool = state.db.clone();
+        let engine = state.templates.clone();
 
         let result = tokio::task::spawn_blocking(move || {
             let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
 
-            conn.transaction::<_, anyhow::Error, _>(|conn| {
+            let order_id = conn.transaction::<_, anyhow::Error, _>(|conn| {
                 // 1. Create order
                 let new_order = NewOrder {
                     id: cuid2::create_id(),
@@ -212,18 +226,33 @@ async fn stripe_webhook(
                     }
                 }
 
-                Ok(())
+                Ok(new_order.id)
             })
             .map_err(|e| e.to_string())?;
 
+            // 4. Queue the receipt (or membership welcome) once the order is
+            // committed, so a problem with the email cannot undo a purchase
+            // Stripe has already charged for.
+            match payment {
+                Some(payment) => {
+                    if let Err(e) = receipts::queue_order_email(&mut conn, &engine, &order_id, payment, &locale) {
+                        tracing::error!("Failed to queue receipt for order {}: {}", order_id, e);
+                    }
+                }
+                None => tracing::warn!("checkout.session.completed missing amount; no receipt sent"),
+            }
+
             Ok::<_, String>(())
         })
         .await
         .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
 
-        if let Err(e) = result {
-            tracing::error!("Failed to process checkout.session.completed: {}", e);
-            // Still return OK to acknowledge receipt - Stripe will retry otherwise
+        match result {
+            Ok(()) => outbox::wake(&state),
+            Err(e) => {
+                tracing::error!("Failed to process checkout.session.completed: {}", e);
+                // Still return OK to acknowledge receipt - Stripe will retry otherwise
+            }
         }
     }
 
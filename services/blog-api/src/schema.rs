@@ -29,6 +29,8 @@ diesel::table! {
         onboarding_completed_at -> Nullable<Timestamp>,
         #[sql_name = "newsletterOptInAt"]
         newsletter_opt_in_at -> Nullable<Timestamp>,
+        #[sql_name = "dmEmailNotifications"]
+        dm_email_notifications -> Bool,
         #[sql_name = "createdAt"]
         created_at -> Timestamp,
         #[sql_name = "updatedAt"]
@@ -102,6 +104,10 @@ diesel::table! {
         content -> Text,
         #[sql_name = "createdAt"]
         created_at -> Timestamp,
+        sources -> Nullable<Jsonb>,
+        #[sql_name = "readAt"]
+        read_at -> Nullable<Timestamp>,
+        incomplete -> Bool,
     }
 }
 
@@ -191,6 +197,227 @@ diesel::table! {
         created_at -> Timestamp,
         #[sql_name = "updatedAt"]
         updated_at -> Timestamp,
+        locale -> Text,
+        source -> Text,
+        #[sql_name = "trackingOptOut"]
+        tracking_opt_out -> Bool,
+        topics -> Array<Text>,
+        frequency -> Text,
+        #[sql_name = "pausedUntil"]
+        paused_until -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "NewsletterSuppression"]
+    newsletter_suppressions (id) {
+        id -> Text,
+        email -> Text,
+        reason -> Text,
+        #[sql_name = "createdAt"]
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "Job"]
+    jobs (id) {
+        id -> Text,
+        kind -> Text,
+        payload -> Jsonb,
+        status -> Text,
+        #[sql_name = "runAt"]
+        run_at -> Timestamp,
+        attempts -> Int4,
+        #[sql_name = "maxAttempts"]
+        max_attempts -> Int4,
+        #[sql_name = "lastError"]
+        last_error -> Nullable<Text>,
+        #[sql_name = "lockedAt"]
+        locked_at -> Nullable<Timestamp>,
+        #[sql_name = "lockedBy"]
+        locked_by -> Nullable<Text>,
+        #[sql_name = "createdAt"]
+        created_at -> Timestamp,
+        #[sql_name = "updatedAt"]
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "NewsletterIssue"]
+    newsletter_issues (id) {
+        id -> Text,
+        subject -> Text,
+        html -> Text,
+        text -> Nullable<Text>,
+        locale -> Text,
+        status -> Text,
+        #[sql_name = "scheduledAt"]
+        scheduled_at -> Nullable<Timestamp>,
+        #[sql_name = "sentAt"]
+        sent_at -> Nullable<Timestamp>,
+        #[sql_name = "jobId"]
+        job_id -> Nullable<Text>,
+        #[sql_name = "createdAt"]
+        created_at -> Timestamp,
+        #[sql_name = "updatedAt"]
+        updated_at -> Timestamp,
+        #[sql_name = "trackingEnabled"]
+        tracking_enabled -> Bool,
+        kind -> Text,
+        topic -> Nullable<Text>,
+        #[sql_name = "membersOnly"]
+        members_only -> Bool,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "NewsletterDigestPost"]
+    newsletter_digest_posts (id) {
+        id -> Text,
+        slug -> Text,
+        locale -> Text,
+        #[sql_name = "issueId"]
+        issue_id -> Text,
+        #[sql_name = "createdAt"]
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "NewsletterDelivery"]
+    newsletter_deliveries (id) {
+        id -> Text,
+        #[sql_name = "issueId"]
+        issue_id -> Text,
+        #[sql_name = "subscriptionId"]
+        subscription_id -> Text,
+        #[sql_name = "tokenHash"]
+        token_hash -> Text,
+        status -> Text,
+        error -> Nullable<Text>,
+        #[sql_name = "sentAt"]
+        sent_at -> Nullable<Timestamp>,
+        #[sql_name = "createdAt"]
+        created_at -> Timestamp,
+        tracked -> Bool,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "NewsletterEvent"]
+    newsletter_events (id) {
+        id -> Text,
+        #[sql_name = "deliveryId"]
+        delivery_id -> Text,
+        #[sql_name = "issueId"]
+        issue_id -> Text,
+        #[sql_name = "type"]
+        type_ -> Text,
+        url -> Nullable<Text>,
+        #[sql_name = "createdAt"]
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "EmailOutbox"]
+    email_outbox (id) {
+        id -> Text,
+        kind -> Text,
+        sender -> Text,
+        recipient -> Text,
+        subject -> Text,
+        html -> Text,
+        text -> Nullable<Text>,
+        status -> Text,
+        attempts -> Int4,
+        #[sql_name = "maxAttempts"]
+        max_attempts -> Int4,
+        #[sql_name = "nextAttemptAt"]
+        next_attempt_at -> Timestamp,
+        #[sql_name = "lastError"]
+        last_error -> Nullable<Text>,
+        #[sql_name = "lockedAt"]
+        locked_at -> Nullable<Timestamp>,
+        #[sql_name = "sentAt"]
+        sent_at -> Nullable<Timestamp>,
+        #[sql_name = "createdAt"]
+        created_at -> Timestamp,
+        #[sql_name = "updatedAt"]
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "ChatUsage"]
+    chat_usage (subject, day) {
+        subject -> Text,
+        day -> Date,
+        questions -> Int4,
+        #[sql_name = "inkSpent"]
+        ink_spent -> Int4,
+        #[sql_name = "updatedAt"]
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "MessageFeedback"]
+    message_feedback (id) {
+        id -> Text,
+        #[sql_name = "messageId"]
+        message_id -> Text,
+        #[sql_name = "userId"]
+        user_id -> Text,
+        rating -> Int4,
+        reason -> Nullable<Text>,
+        comment -> Nullable<Text>,
+        query -> Text,
+        locale -> Text,
+        #[sql_name = "sourceSlugs"]
+        source_slugs -> Array<Text>,
+        #[sql_name = "createdAt"]
+        created_at -> Timestamp,
+        #[sql_name = "updatedAt"]
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "ChatAnswerCache"]
+    chat_answer_cache (query_key, locale) {
+        #[sql_name = "queryKey"]
+        query_key -> Text,
+        locale -> Text,
+        query -> Text,
+        answer -> Text,
+        sources -> Nullable<Jsonb>,
+        done -> Nullable<Jsonb>,
+        hits -> Int4,
+        #[sql_name = "createdAt"]
+        created_at -> Timestamp,
+        #[sql_name = "expiresAt"]
+        expires_at -> Timestamp,
+        #[sql_name = "lastHitAt"]
+        last_hit_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    #[sql_name = "ChatGuardHit"]
+    chat_guard_hits (id) {
+        id -> Text,
+        rule -> Text,
+        action -> Text,
+        detail -> Nullable<Text>,
+        #[sql_name = "userId"]
+        user_id -> Nullable<Text>,
+        locale -> Text,
+        query -> Text,
+        #[sql_name = "createdAt"]
+        created_at -> Timestamp,
     }
 }
 
@@ -198,10 +425,17 @@ diesel::joinable!(accounts -> users (user_id));
 diesel::joinable!(sessions -> users (user_id));
 diesel::joinable!(threads -> users (user_id));
 diesel::joinable!(messages -> threads (thread_id));
+diesel::joinable!(message_feedback -> messages (message_id));
+diesel::joinable!(message_feedback -> users (user_id));
+diesel::joinable!(chat_guard_hits -> users (user_id));
 diesel::joinable!(orders -> users (user_id));
 diesel::joinable!(orders -> products (product_id));
 diesel::joinable!(marginalia -> users (user_id));
 diesel::joinable!(newsletter_subscriptions -> users (user_id));
+diesel::joinable!(newsletter_deliveries -> newsletter_issues (issue_id));
+diesel::joinable!(newsletter_deliveries -> newsletter_subscriptions (subscription_id));
+diesel::joinable!(newsletter_events -> newsletter_deliveries (delivery_id));
+diesel::joinable!(newsletter_digest_posts -> newsletter_issues (issue_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     users,
@@ -215,4 +449,15 @@ diesel::allow_tables_to_appear_in_same_query!(
     marginalia,
     embeddings,
     newsletter_subscriptions,
+    newsletter_suppressions,
+    jobs,
+    newsletter_issues,
+    newsletter_deliveries,
+    newsletter_events,
+    newsletter_digest_posts,
+    email_outbox,
+    chat_usage,
+    message_feedback,
+    chat_answer_cache,
+    chat_guard_hits,
 );
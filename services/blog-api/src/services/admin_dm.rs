@@ -0,0 +1,194 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::dm_notify;
+use super::threads::{touch, ADMIN_DM_THREAD_TITLE, ROLE_ADMIN, ROLE_USER};
+use crate::models::{Message, NewMessage, NewThread};
+use crate::schema::{messages, threads};
+
+/// Longest admin reply accepted.
+pub const MAX_REPLY_CHARS: usize = 5000;
+
+#[derive(thiserror::Error, Debug)]
+pub enum DmError {
+    #[error("thread not found")]
+    NotFound,
+
+    #[error("DB connection error: {0}")]
+    Connection(String),
+
+    #[error(transparent)]
+    Db(#[from] diesel::result::Error),
+}
+
+/// Which side of the conversation is reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Reader,
+    Admin,
+}
+
+impl Side {
+    /// Role of the messages this side sends.
+    fn role(self) -> &'static str {
+        match self {
+            Side::Reader => ROLE_USER,
+            Side::Admin => ROLE_ADMIN,
+        }
+    }
+
+    /// Role of the messages this side receives.
+    pub(super) fn incoming(self) -> &'static str {
+        match self {
+            Side::Reader => ROLE_ADMIN,
+            Side::Admin => ROLE_USER,
+        }
+    }
+}
+
+pub struct Posted {
+    pub thread_id: String,
+    pub message_id: String,
+}
+
+/// The reader's DM thread, if they have written before.
+pub fn thread_of(conn: &mut PgConnection, user_id: &str) -> QueryResult<Option<String>> {
+    threads::table
+        .filter(threads::user_id.eq(Some(user_id)))
+        .filter(threads::title.eq(Some(ADMIN_DM_THREAD_TITLE)))
+        .select(threads::id)
+        .first(conn)
+        .optional()
+}
+
+/// The owner of a DM thread, or `NotFound` if `thread_id` isn't one.
+pub fn owner_of(conn: &mut PgConnection, thread_id: &str) -> Result<String, DmError> {
+    threads::table
+        .filter(threads::id.eq(thread_id))
+        .filter(threads::title.eq(Some(ADMIN_DM_THREAD_TITLE)))
+        .select(threads::user_id)
+        .first::<Option<String>>(conn)
+        .optional()?
+        .flatten()
+        .ok_or(DmError::NotFound)
+}
+
+/// Stores a message and schedules the email telling the other side.
+fn insert(conn: &mut PgConnection, thread_id: &str, side: Side, content: &str) -> QueryResult<String> {
+    let id = cuid2::create_id();
+    let sent_at: NaiveDateTime = diesel::insert_into(messages::table)
+        .values(&NewMessage {
+            id: id.clone(),
+            thread_id: thread_id.to_string(),
+            role: side.role().to_string(),
+            content: content.to_string(),
+            sources: None,
+            incomplete: false,
+        })
+        .returning(messages::created_at)
+        .get_result(conn)?;
+    touch(conn, thread_id)?;
+    let to = match side {
+        Side::Reader => Side::Admin,
+        Side::Admin => Side::Reader,
+    };
+    dm_notify::schedule(conn, thread_id, to, sent_at)?;
+    Ok(id)
+}
+
+/// Adds a reader's message, creating their DM thread on first use.
+pub fn send_from_reader(conn: &mut PgConnection, user_id: &str, content: &str) -> QueryResult<Posted> {
+    conn.transaction(|conn| {
+        let thread_id = match thread_of(conn, user_id)? {
+            Some(id) => id,
+            None => {
+                let thread = NewThread {
+                    id: cuid2::create_id(),
+                    title: Some(ADMIN_DM_THREAD_TITLE.to_string()),
+                    user_id: Some(user_id.to_string()),
+                };
+                diesel::insert_into(threads::table)
+                    .values(&thread)
+                    .execute(conn)?;
+                thread.id
+            }
+        };
+
+        let message_id = insert(conn, &thread_id, Side::Reader, content)?;
+        Ok(Posted { thread_id, message_id })
+    })
+}
+
+/// Adds an admin reply. Replying also counts as having read the thread.
+pub fn reply_as_admin(conn: &mut PgConnection, thread_id: &str, content: &str) -> Result<Posted, DmError> {
+    conn.transaction(|conn| {
+        owner_of(conn, thread_id)?;
+        mark_read(conn, thread_id, Side::Admin)?;
+        let message_id = insert(conn, thread_id, Side::Admin, content)?;
+        Ok(Posted {
+            thread_id: thread_id.to_string(),
+            message_id,
+        })
+    })
+}
+
+/// Marks everything the other side sent as read by `side`.
+pub fn mark_read(conn: &mut PgConnection, thread_id: &str, side: Side) -> QueryResult<usize> {
+    diesel::update(
+        messages::table
+            .filter(messages::thread_id.eq(thread_id))
+            .filter(messages::role.eq(side.incoming()))
+            .filter(messages::read_at.is_null()),
+    )
+    .set(messages::read_at.eq(Some(Utc::now().naive_utc())))
+    .execute(conn)
+}
+
+/// Marks the latest message from the other side as unread again.
+pub fn mark_unread(conn: &mut PgConnection, thread_id: &str, side: Side) -> QueryResult<usize> {
+    let latest: Option<String> = messages::table
+        .filter(messages::thread_id.eq(thread_id))
+        .filter(messages::role.eq(side.incoming()))
+        .order(messages::created_at.desc())
+        .select(messages::id)
+        .first(conn)
+        .optional()?;
+
+    match latest {
+        Some(id) => diesel::update(messages::table.find(id))
+            .set(messages::read_at.eq(None::<chrono::NaiveDateTime>))
+            .execute(conn),
+        None => Ok(0),
+    }
+}
+
+/// Messages `side` has not read yet.
+pub fn unread_count(conn: &mut PgConnection, thread_id: &str, side: Side) -> QueryResult<i64> {
+    messages::table
+        .filter(messages::thread_id.eq(thread_id))
+        .filter(messages::role.eq(side.incoming()))
+        .filter(messages::read_at.is_null())
+        .count()
+        .get_result(conn)
+}
+
+/// A page of a DM thread, newest page first and each page oldest first.
+/// The flag tells whether an older page exists.
+pub fn page(conn: &mut PgConnection, thread_id: &str, page: i64, per_page: i64) -> QueryResult<(Vec<Message>, bool)> {
+    // One extra row tells us whether an older page exists.
+    let mut rows: Vec<Message> = messages::table
+        .filter(messages::thread_id.eq(thread_id))
+        .order((messages::created_at.desc(), messages::id.desc()))
+        .limit(per_page + 1)
+        .offset((page - 1) * per_page)
+        .select(Message::as_select())
+        .load(conn)?;
+
+    let has_more = rows.len() as i64 > per_page;
+    rows.truncate(per_page as usize);
+    rows.reverse();
+    Ok((rows, has_more))
+}
@@ -0,0 +1,164 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use std::time::Duration;
+
+use crate::models::{CachedAnswer, NewCachedAnswer};
+use crate::schema::chat_answer_cache;
+
+pub struct CacheConfig {
+    pub enabled: bool,
+    pub ttl: Duration,
+    /// Rough size of each replayed content event, in characters.
+    pub replay_chunk_chars: usize,
+    /// Pause between replayed content events.
+    pub replay_delay: Duration,
+}
+
+impl CacheConfig {
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            enabled: std::env::var("CHAT_CACHE_ENABLED")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+            ttl: Duration::from_secs(var("CHAT_CACHE_TTL_SECS", 24 * 3600)),
+            replay_chunk_chars: var("CHAT_CACHE_REPLAY_CHUNK_CHARS", 24).max(1) as usize,
+            replay_delay: Duration::from_millis(var("CHAT_CACHE_REPLAY_DELAY_MS", 30)),
+        }
+    }
+}
+
+/// Where an answer is cached: the normalized question and its locale, plus
+/// the wording that was asked, kept for the admin view.
+#[derive(Debug, Clone)]
+pub struct CacheKey {
+    pub query_key: String,
+    pub locale: String,
+    pub query: String,
+}
+
+impl CacheKey {
+    pub fn new(query: &str, locale: &str) -> Self {
+        Self {
+            query_key: normalize_query(query),
+            locale: locale.to_string(),
+            query: query.to_string(),
+        }
+    }
+}
+
+/// Case, spacing and closing punctuation don't change the question:
+/// "Who are you?" and "who are  you" share an entry.
+pub fn normalize_query(query: &str) -> String {
+    let text = query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    text.trim_end_matches(['?', '!', '.', '？', '！', '。', ' ']).to_string()
+}
+
+/// A live entry for `key`, counting the hit.
+pub fn lookup(conn: &mut PgConnection, key: &CacheKey) -> QueryResult<Option<CachedAnswer>> {
+    let now = Utc::now().naive_utc();
+    let target = chat_answer_cache::table
+        .filter(chat_answer_cache::query_key.eq(&key.query_key))
+        .filter(chat_answer_cache::locale.eq(&key.locale))
+        .filter(chat_answer_cache::expires_at.gt(now));
+
+    diesel::update(target)
+        .set((
+            chat_answer_cache::hits.eq(chat_answer_cache::hits + 1),
+            chat_answer_cache::last_hit_at.eq(Some(now)),
+        ))
+        .returning(CachedAnswer::as_returning())
+        .get_result(conn)
+        .optional()
+}
+
+/// Caches a finished answer, replacing any entry for the same question.
+pub fn store(
+    conn: &mut PgConnection,
+    key: &CacheKey,
+    answer: &str,
+    sources: Option<serde_json::Value>,
+    done: Option<serde_json::Value>,
+    ttl: Duration,
+) -> QueryResult<()> {
+    let now = Utc::now().naive_utc();
+    let expires_at = now + chrono::Duration::from_std(ttl).unwrap_or_else(|_| chrono::Duration::days(1));
+
+    let row = NewCachedAnswer {
+        query_key: key.query_key.clone(),
+        locale: key.locale.clone(),
+        query: key.query.clone(),
+        answer: answer.to_string(),
+        sources,
+        done,
+        expires_at,
+    };
+
+    conn.transaction(|conn| {
+        purge_expired(conn)?;
+
+        diesel::insert_into(chat_answer_cache::table)
+            .values(&row)
+            .on_conflict((chat_answer_cache::query_key, chat_answer_cache::locale))
+            .do_update()
+            .set((
+                chat_answer_cache::query.eq(&row.query),
+                chat_answer_cache::answer.eq(&row.answer),
+                chat_answer_cache::sources.eq(&row.sources),
+                chat_answer_cache::done.eq(&row.done),
+                chat_answer_cache::hits.eq(0),
+                chat_answer_cache::created_at.eq(now),
+                chat_answer_cache::expires_at.eq(expires_at),
+                chat_answer_cache::last_hit_at.eq::<Option<NaiveDateTime>>(None),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    })
+}
+
+/// Removes entries: one question (in one or every locale), one locale, or
+/// everything. Returns how many were removed.
+pub fn purge(conn: &mut PgConnection, query: Option<&str>, locale: Option<&str>) -> QueryResult<usize> {
+    let mut target = diesel::delete(chat_answer_cache::table).into_boxed();
+    if let Some(query) = query {
+        target = target.filter(chat_answer_cache::query_key.eq(normalize_query(query)));
+    }
+    if let Some(locale) = locale {
+        target = target.filter(chat_answer_cache::locale.eq(locale.to_string()));
+    }
+    target.execute(conn)
+}
+
+pub fn purge_expired(conn: &mut PgConnection) -> QueryResult<usize> {
+    diesel::delete(chat_answer_cache::table.filter(chat_answer_cache::expires_at.le(Utc::now().naive_utc())))
+        .execute(conn)
+}
+
+/// Splits a cached answer into streaming-sized pieces, breaking after
+/// whitespace where possible so replayed text arrives word by word.
+pub fn replay_chunks(text: &str, size: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut count = 0;
+
+    for c in text.chars() {
+        current.push(c);
+        count += 1;
+        if (count >= size && c.is_whitespace()) || count >= size * 2 {
+            chunks.push(std::mem::take(&mut current));
+            count = 0;
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
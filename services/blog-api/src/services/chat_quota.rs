@@ -0,0 +1,288 @@
+use chrono::{Days, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use super::membership::is_paid_member;
+use crate::schema::{chat_usage, users};
+
+pub const PLAN_ANONYMOUS: &str = "anonymous";
+pub const PLAN_USER: &str = "user";
+pub const PLAN_MEMBER: &str = "member";
+
+/// Who a question is counted against.
+#[derive(Debug, Clone)]
+pub enum Subject {
+    User(String),
+    Ip(String),
+}
+
+impl Subject {
+    fn key(&self) -> String {
+        match self {
+            Subject::User(id) => format!("user:{}", id),
+            Subject::Ip(addr) => format!("ip:{}", addr),
+        }
+    }
+}
+
+pub struct QuotaConfig {
+    pub enabled: bool,
+    /// Free questions per KST day for each anonymous IP address.
+    pub anon_daily: i32,
+    /// Free questions per KST day for each signed-in reader.
+    pub user_daily: i32,
+    /// Free questions per day for members; `None` is unlimited.
+    pub member_daily: Option<i32>,
+    /// Ink points per question once the free questions are used up.
+    /// Zero turns paid questions off.
+    pub ink_cost: i32,
+}
+
+impl QuotaConfig {
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: i32| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            enabled: std::env::var("CHAT_QUOTA_ENABLED")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+            anon_daily: var("CHAT_ANON_DAILY_LIMIT", 5).max(0),
+            user_daily: var("CHAT_USER_DAILY_LIMIT", 20).max(0),
+            member_daily: Some(var("CHAT_MEMBER_DAILY_LIMIT", 0)).filter(|limit| *limit > 0),
+            ink_cost: var("CHAT_INK_COST", 1).max(0),
+        }
+    }
+}
+
+/// Where a reader stands for today, returned with quota errors and by
+/// `POST /api/chat/quota`.
+#[derive(Serialize, Debug, Clone)]
+pub struct QuotaStatus {
+    pub plan: &'static str,
+    /// `None` when the plan is unlimited.
+    pub daily_limit: Option<i32>,
+    pub used: i32,
+    pub remaining: Option<i32>,
+    /// Ink points per question past the daily limit; signed-in readers only.
+    pub ink_cost: Option<i32>,
+    pub ink_points: Option<i32>,
+    /// Start of the next KST day, in UTC.
+    pub resets_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Charge {
+    Free,
+    Ink(i32),
+}
+
+/// A question that was counted, kept so it can be refunded if the answer
+/// never starts.
+pub struct Ticket {
+    key: String,
+    day: NaiveDate,
+    user_id: Option<String>,
+    pub charge: Charge,
+    pub status: QuotaStatus,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum QuotaError {
+    #[error("daily chat limit reached")]
+    Exhausted(QuotaStatus),
+
+    #[error("not enough ink points")]
+    InsufficientInk(QuotaStatus),
+
+    #[error("DB connection error: {0}")]
+    Connection(String),
+
+    #[error(transparent)]
+    Db(#[from] diesel::result::Error),
+}
+
+/// Today's KST date and when it ends, in UTC.
+fn today() -> (NaiveDate, NaiveDateTime) {
+    let kst = FixedOffset::east_opt(9 * 3600).expect("valid offset");
+    let day = Utc::now().with_timezone(&kst).date_naive();
+    let resets_at = (day + Days::new(1))
+        .and_hms_opt(0, 0, 0)
+        .expect("valid time")
+        .and_local_timezone(kst)
+        .single()
+        .expect("fixed offset")
+        .naive_utc();
+    (day, resets_at)
+}
+
+fn plan(conn: &mut PgConnection, subject: &Subject, config: &QuotaConfig) -> QueryResult<(&'static str, Option<i32>)> {
+    Ok(match subject {
+        Subject::Ip(_) => (PLAN_ANONYMOUS, Some(config.anon_daily)),
+        Subject::User(id) if is_paid_member(conn, id)? => (PLAN_MEMBER, config.member_daily),
+        Subject::User(_) => (PLAN_USER, Some(config.user_daily)),
+    })
+}
+
+fn ink_points(conn: &mut PgConnection, subject: &Subject) -> QueryResult<Option<i32>> {
+    match subject {
+        Subject::User(id) => users::table
+            .filter(users::id.eq(id))
+            .select(users::ink_points)
+            .first(conn)
+            .optional(),
+        Subject::Ip(_) => Ok(None),
+    }
+}
+
+fn build_status(
+    plan: &'static str,
+    daily_limit: Option<i32>,
+    used: i32,
+    subject: &Subject,
+    ink_points: Option<i32>,
+    config: &QuotaConfig,
+    resets_at: NaiveDateTime,
+) -> QuotaStatus {
+    QuotaStatus {
+        plan,
+        daily_limit,
+        used,
+        remaining: daily_limit.map(|limit| (limit - used).max(0)),
+        ink_cost: match subject {
+            Subject::User(_) if config.ink_cost > 0 => Some(config.ink_cost),
+            _ => None,
+        },
+        ink_points,
+        resets_at,
+    }
+}
+
+/// Today's usage without counting anything.
+pub fn status(conn: &mut PgConnection, subject: &Subject, config: &QuotaConfig) -> QueryResult<QuotaStatus> {
+    let (day, resets_at) = today();
+    let used: i32 = chat_usage::table
+        .find((subject.key(), day))
+        .select(chat_usage::questions)
+        .first(conn)
+        .optional()?
+        .unwrap_or(0);
+    let (plan, limit) = plan(conn, subject, config)?;
+    let points = ink_points(conn, subject)?;
+
+    Ok(build_status(plan, limit, used, subject, points, config, resets_at))
+}
+
+/// Counts one question. Free questions come first; after that a signed-in
+/// reader pays `ink_cost` points, deducted in the same transaction.
+pub fn consume(conn: &mut PgConnection, subject: &Subject, config: &QuotaConfig) -> Result<Ticket, QuotaError> {
+    conn.transaction(|conn| {
+        let (day, resets_at) = today();
+        let key = subject.key();
+        let now = Utc::now().naive_utc();
+
+        diesel::insert_into(chat_usage::table)
+            .values((chat_usage::subject.eq(&key), chat_usage::day.eq(day)))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        // Locks today's row so concurrent questions are counted one by one.
+        let used: i32 = chat_usage::table
+            .find((&key, day))
+            .select(chat_usage::questions)
+            .for_update()
+            .first(conn)?;
+
+        let (plan, limit) = plan(conn, subject, config)?;
+
+        let (charge, points) = match (limit, subject) {
+            (None, _) => (Charge::Free, None),
+            (Some(limit), _) if used < limit => (Charge::Free, None),
+            (Some(_), Subject::User(id)) if config.ink_cost > 0 => {
+                let remaining: Option<i32> = diesel::update(
+                    users::table
+                        .filter(users::id.eq(id))
+                        .filter(users::ink_points.ge(config.ink_cost)),
+                )
+                .set(users::ink_points.eq(users::ink_points - config.ink_cost))
+                .returning(users::ink_points)
+                .get_result(conn)
+                .optional()?;
+
+                match remaining {
+                    Some(points) => (Charge::Ink(config.ink_cost), Some(points)),
+                    None => {
+                        let points = ink_points(conn, subject)?;
+                        let status = build_status(plan, limit, used, subject, points, config, resets_at);
+                        return Err(QuotaError::InsufficientInk(status));
+                    }
+                }
+            }
+            (Some(_), _) => {
+                let points = ink_points(conn, subject)?;
+                let status = build_status(plan, limit, used, subject, points, config, resets_at);
+                return Err(QuotaError::Exhausted(status));
+            }
+        };
+
+        let spent = match charge {
+            Charge::Ink(points) => points,
+            Charge::Free => 0,
+        };
+        diesel::update(chat_usage::table.find((&key, day)))
+            .set((
+                chat_usage::questions.eq(chat_usage::questions + 1),
+                chat_usage::ink_spent.eq(chat_usage::ink_spent + spent),
+                chat_usage::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+
+        let points = match points {
+            Some(points) => Some(points),
+            None => ink_points(conn, subject)?,
+        };
+
+        Ok(Ticket {
+            status: build_status(plan, limit, used + 1, subject, points, config, resets_at),
+            user_id: match subject {
+                Subject::User(id) => Some(id.clone()),
+                Subject::Ip(_) => None,
+            },
+            key,
+            day,
+            charge,
+        })
+    })
+}
+
+/// Gives a question back when the RAG service could not be reached.
+pub fn refund(conn: &mut PgConnection, ticket: &Ticket) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let spent = match ticket.charge {
+            Charge::Ink(points) => points,
+            Charge::Free => 0,
+        };
+
+        diesel::update(chat_usage::table.find((&ticket.key, ticket.day)))
+            .filter(chat_usage::questions.gt(0))
+            .set((
+                chat_usage::questions.eq(chat_usage::questions - 1),
+                chat_usage::ink_spent.eq(chat_usage::ink_spent - spent),
+                chat_usage::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+
+        if let (Charge::Ink(points), Some(user_id)) = (ticket.charge, &ticket.user_id) {
+            diesel::update(users::table.filter(users::id.eq(user_id)))
+                .set(users::ink_points.eq(users::ink_points + points))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    })
+}
@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Limits for talking to the RAG service.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamTimeouts {
+    /// Establishing the TCP/TLS connection.
+    pub connect: Duration,
+    /// From sending the question until the first piece of answer content.
+    pub first_token: Duration,
+    /// Longest silence between upstream chunks once content is flowing.
+    pub idle: Duration,
+    /// Keep-alive comment interval towards the client.
+    pub heartbeat: Duration,
+}
+
+impl StreamTimeouts {
+    pub fn from_env() -> Self {
+        let secs = |name: &str, default: u64| {
+            let secs = std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(default);
+            Duration::from_secs(secs)
+        };
+
+        Self {
+            connect: secs("CHAT_CONNECT_TIMEOUT_SECS", 5),
+            first_token: secs("CHAT_FIRST_TOKEN_TIMEOUT_SECS", 30),
+            idle: secs("CHAT_IDLE_TIMEOUT_SECS", 20),
+            heartbeat: secs("CHAT_HEARTBEAT_SECS", 15),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamOutcome {
+    Completed,
+    /// The client went away before the answer finished.
+    Cancelled,
+    TimedOut,
+    /// The RAG service refused the request or broke off the stream.
+    Failed,
+}
+
+impl StreamOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StreamOutcome::Completed => "completed",
+            StreamOutcome::Cancelled => "cancelled",
+            StreamOutcome::TimedOut => "timed_out",
+            StreamOutcome::Failed => "failed",
+        }
+    }
+}
+
+/// Process-wide counters for chat requests, reset on restart.
+#[derive(Default)]
+pub struct ChatMetrics {
+    started: AtomicU64,
+    completed: AtomicU64,
+    cancelled: AtomicU64,
+    timed_out: AtomicU64,
+    failed: AtomicU64,
+    completed_millis: AtomicU64,
+    cache_hits: AtomicU64,
+}
+
+#[derive(Serialize)]
+pub struct ChatMetricsSnapshot {
+    pub started: u64,
+    pub completed: u64,
+    pub cancelled: u64,
+    pub timed_out: u64,
+    pub failed: u64,
+    pub in_flight: u64,
+    /// Mean wall time of completed answers.
+    pub avg_completed_ms: Option<u64>,
+    /// Questions answered from the answer cache, not counted above.
+    pub cache_hits: u64,
+}
+
+impl ChatMetrics {
+    fn record(&self, outcome: StreamOutcome, elapsed: Duration) {
+        let counter = match outcome {
+            StreamOutcome::Completed => {
+                self.completed_millis
+                    .fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed);
+                &self.completed
+            }
+            StreamOutcome::Cancelled => &self.cancelled,
+            StreamOutcome::TimedOut => &self.timed_out,
+            StreamOutcome::Failed => &self.failed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> ChatMetricsSnapshot {
+        let started = self.started.load(Ordering::Relaxed);
+        let completed = self.completed.load(Ordering::Relaxed);
+        let cancelled = self.cancelled.load(Ordering::Relaxed);
+        let timed_out = self.timed_out.load(Ordering::Relaxed);
+        let failed = self.failed.load(Ordering::Relaxed);
+
+        ChatMetricsSnapshot {
+            started,
+            completed,
+            cancelled,
+            timed_out,
+            failed,
+            in_flight: started.saturating_sub(completed + cancelled + timed_out + failed),
+            avg_completed_ms: (completed > 0).then(|| self.completed_millis.load(Ordering::Relaxed) / completed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Tracks one request to the RAG service. Dropping it without calling
+/// [`StreamGuard::finish`] counts as a cancellation: axum drops the
+/// response stream (and with it the upstream connection) when the client
+/// disconnects.
+pub struct StreamGuard {
+    metrics: Arc<ChatMetrics>,
+    started: Instant,
+    outcome: Option<StreamOutcome>,
+}
+
+impl StreamGuard {
+    pub fn start(metrics: Arc<ChatMetrics>) -> Self {
+        metrics.started.fetch_add(1, Ordering::Relaxed);
+        Self {
+            metrics,
+            started: Instant::now(),
+            outcome: None,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Sets how the request ended; the first call wins.
+    pub fn finish(&mut self, outcome: StreamOutcome) {
+        self.outcome.get_or_insert(outcome);
+    }
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        let outcome = self.outcome.unwrap_or(StreamOutcome::Cancelled);
+        let elapsed = self.started.elapsed();
+        self.metrics.record(outcome, elapsed);
+        tracing::info!("chat stream {} after {} ms", outcome.as_str(), elapsed.as_millis());
+    }
+}
@@ -0,0 +1,271 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::dsl::max;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{Jsonb, Text, Timestamp};
+use std::sync::Arc;
+
+use super::jobs;
+use super::newsletter::{
+    base_url, next_saturday_morning_kst, SendIssuePayload, ISSUE_KIND_DIGEST, ISSUE_SCHEDULED,
+};
+use super::templates::{DigestPost, NewsletterDigest, TemplateEngine};
+use super::AppState;
+use crate::models::{Job, NewNewsletterIssue, NewsletterIssue};
+use crate::schema::{jobs as jobs_table, newsletter_digest_posts, newsletter_issues};
+
+const DIGEST_LOCALES: &[&str] = &["ko", "en"];
+const EXCERPT_CHARS: usize = 200;
+
+pub struct DigestConfig {
+    pub enabled: bool,
+    pub cadence_days: i64,
+    /// Schedule generated digests right away instead of leaving them as drafts.
+    pub auto_send: bool,
+}
+
+impl DigestConfig {
+    pub fn from_env() -> Self {
+        let flag = |name: &str| {
+            std::env::var(name)
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false)
+        };
+
+        Self {
+            enabled: flag("NEWSLETTER_DIGEST_ENABLED"),
+            cadence_days: std::env::var("NEWSLETTER_DIGEST_CADENCE_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|d| *d > 0)
+                .unwrap_or(7),
+            auto_send: flag("NEWSLETTER_DIGEST_AUTO_SEND"),
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+struct NewPostRow {
+    #[diesel(sql_type = Text)]
+    slug: String,
+    #[diesel(sql_type = Jsonb)]
+    metadata: serde_json::Value,
+    #[diesel(sql_type = Text)]
+    content: String,
+}
+
+/// Slugs whose first chunk was indexed after `since` and that no earlier
+/// digest has announced. `Embedding` holds several chunks per slug, so the
+/// earliest chunk stands in for the post.
+fn find_new_posts(conn: &mut PgConnection, locale: &str, since: NaiveDateTime) -> QueryResult<Vec<NewPostRow>> {
+    sql_query(
+        r#"
+        SELECT slug, metadata, content
+        FROM (
+            SELECT DISTINCT ON (e.slug)
+                e.slug,
+                e.metadata,
+                e.content,
+                MIN(e."createdAt") OVER (PARTITION BY e.slug) AS first_indexed
+            FROM "Embedding" e
+            WHERE e.locale = $1 AND e."contentType" = 'blog'
+            ORDER BY e.slug, e."createdAt" ASC, e.id ASC
+        ) p
+        WHERE p.first_indexed > $2
+          AND NOT EXISTS (
+              SELECT 1 FROM "NewsletterDigestPost" d
+              WHERE d.slug = p.slug AND d.locale = $1
+          )
+        ORDER BY p.first_indexed ASC
+        "#,
+    )
+    .bind::<Text, _>(locale)
+    .bind::<Timestamp, _>(since)
+    .load(conn)
+}
+
+fn metadata_str<'a>(metadata: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    metadata
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+fn to_digest_post(row: &NewPostRow, locale: &str) -> DigestPost {
+    let title = metadata_str(&row.metadata, "title").unwrap_or(&row.slug).to_string();
+
+    let excerpt = ["description", "excerpt", "summary"]
+        .iter()
+        .find_map(|key| metadata_str(&row.metadata, key))
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            let content = row.content.trim();
+            if content.chars().count() > EXCERPT_CHARS {
+                format!("{}…", content.chars().take(EXCERPT_CHARS).collect::<String>().trim_end())
+            } else {
+                content.to_string()
+            }
+        });
+
+    // The indexer stores site-relative URLs such as `/catalog/<slug>`.
+    let url = match metadata_str(&row.metadata, "url") {
+        Some(u) if u.starts_with("http://") || u.starts_with("https://") => u.to_string(),
+        Some(u) if u.starts_with('/') => format!("{}/{}{}", base_url(), locale, u),
+        _ => format!("{}/{}/catalog/{}", base_url(), locale, row.slug),
+    };
+
+    DigestPost { title, excerpt, url }
+}
+
+/// Creates a digest issue for `locale` from newly indexed posts.
+/// Returns `None` when there is nothing new to announce.
+pub fn create_digest(
+    conn: &mut PgConnection,
+    engine: &TemplateEngine,
+    locale: &str,
+    config: &DigestConfig,
+    auto_send: bool,
+) -> Result<Option<NewsletterIssue>, anyhow::Error> {
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        let now = Utc::now().naive_utc();
+
+        let last_digest: Option<NaiveDateTime> = newsletter_issues::table
+            .filter(newsletter_issues::kind.eq(ISSUE_KIND_DIGEST))
+            .filter(newsletter_issues::locale.eq(locale))
+            .select(max(newsletter_issues::created_at))
+            .first(conn)?;
+        let since = last_digest.unwrap_or(now - chrono::Duration::days(config.cadence_days));
+
+        let rows = find_new_posts(conn, locale, since)?;
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        let posts: Vec<DigestPost> = rows.iter().map(|row| to_digest_post(row, locale)).collect();
+        let content = engine.render(&NewsletterDigest { posts }, locale)?;
+
+        let issue: NewsletterIssue = diesel::insert_into(newsletter_issues::table)
+            .values(&NewNewsletterIssue {
+                id: cuid2::create_id(),
+                subject: content.subject,
+                html: content.html,
+                text: Some(content.text),
+                locale: locale.to_string(),
+                tracking_enabled: false,
+                kind: ISSUE_KIND_DIGEST.to_string(),
+                topic: None,
+                members_only: false,
+            })
+            .get_result(conn)?;
+
+        for row in &rows {
+            diesel::insert_into(newsletter_digest_posts::table)
+                .values((
+                    newsletter_digest_posts::id.eq(cuid2::create_id()),
+                    newsletter_digest_posts::slug.eq(&row.slug),
+                    newsletter_digest_posts::locale.eq(locale),
+                    newsletter_digest_posts::issue_id.eq(&issue.id),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+
+        if !auto_send {
+            return Ok(Some(issue));
+        }
+
+        let payload = serde_json::to_value(SendIssuePayload {
+            issue_id: issue.id.clone(),
+        })?;
+        let job_id = jobs::enqueue(conn, jobs::KIND_NEWSLETTER_SEND, payload, now)?;
+
+        let issue = diesel::update(newsletter_issues::table.filter(newsletter_issues::id.eq(&issue.id)))
+            .set((
+                newsletter_issues::status.eq(ISSUE_SCHEDULED),
+                newsletter_issues::scheduled_at.eq(Some(now)),
+                newsletter_issues::job_id.eq(Some(job_id)),
+                newsletter_issues::updated_at.eq(now),
+            ))
+            .get_result(conn)?;
+
+        Ok(Some(issue))
+    })
+}
+
+fn has_scheduled_digest(conn: &mut PgConnection) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::exists(
+        jobs_table::table
+            .filter(jobs_table::kind.eq(jobs::KIND_NEWSLETTER_DIGEST))
+            .filter(jobs_table::status.eq_any([jobs::STATUS_PENDING, jobs::STATUS_RUNNING])),
+    ))
+    .get_result(conn)
+}
+
+pub async fn run_digest_job(state: &Arc<AppState>, job: &Job) -> Result<(), String> {
+    let pool = state.db.clone();
+    let engine = state.templates.clone();
+    let run_at = job.run_at;
+
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        let config = DigestConfig::from_env();
+
+        for locale in DIGEST_LOCALES {
+            match create_digest(&mut conn, &engine, locale, &config, config.auto_send)
+                .map_err(|e| format!("Digest error ({}): {}", locale, e))?
+            {
+                Some(issue) => tracing::info!("Created {} digest issue {} ({})", locale, issue.id, issue.status),
+                None => tracing::info!("No new posts for the {} digest", locale),
+            }
+        }
+
+        if config.enabled {
+            // Keep the original slot (e.g. Saturday morning) unless we fell behind.
+            let now = Utc::now().naive_utc();
+            let mut next = run_at + chrono::Duration::days(config.cadence_days);
+            if next <= now {
+                next = now + chrono::Duration::days(config.cadence_days);
+            }
+            jobs::enqueue(&mut conn, jobs::KIND_NEWSLETTER_DIGEST, serde_json::json!({}), next)
+                .map_err(|e| format!("DB insert error: {}", e))?;
+        }
+
+        Ok(())
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)))
+}
+
+/// Makes sure a digest job is queued when `NEWSLETTER_DIGEST_ENABLED=true`.
+/// The first run lands on the next Saturday 09:00 KST.
+pub fn ensure_scheduled(state: Arc<AppState>) {
+    if !DigestConfig::from_env().enabled {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let pool = state.db.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+
+            if has_scheduled_digest(&mut conn).map_err(|e| format!("DB query error: {}", e))? {
+                return Ok(None);
+            }
+
+            let run_at = next_saturday_morning_kst(Utc::now());
+            jobs::enqueue(&mut conn, jobs::KIND_NEWSLETTER_DIGEST, serde_json::json!({}), run_at)
+                .map(|_| Some(run_at))
+                .map_err(|e| format!("DB insert error: {}", e))
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+
+        match result {
+            Ok(Some(run_at)) => tracing::info!("Scheduled newsletter digest for {}", run_at),
+            Ok(None) => {}
+            Err(e) => tracing::error!("Failed to schedule newsletter digest: {}", e),
+        }
+    });
+}
@@ -0,0 +1,259 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::admin_dm::Side;
+use super::email::auth_from;
+use super::jobs;
+use super::mailer::OutgoingEmail;
+use super::newsletter::{base_url, newsletter_locale};
+use super::outbox;
+use super::receipts::format_kst;
+use super::templates::{DmExcerpt, DmNotification, TemplateEngine};
+use super::AppState;
+use crate::models::Job;
+use crate::schema::{jobs as jobs_table, messages, newsletter_subscriptions, threads, users};
+
+/// Longest part of a message quoted in a notification.
+const EXCERPT_CHARS: usize = 600;
+
+pub struct NotifyConfig {
+    pub enabled: bool,
+    /// Messages that arrive this soon after the first one of a burst go out
+    /// in the same email.
+    pub window: Duration,
+    /// Where reader messages are announced. Without it the admin is not emailed.
+    pub admin_email: Option<String>,
+    pub admin_locale: &'static str,
+}
+
+impl NotifyConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var("DM_NOTIFY_ENABLED")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+            window: Duration::from_secs(
+                std::env::var("DM_NOTIFY_WINDOW_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(300),
+            ),
+            admin_email: std::env::var("ADMIN_NOTIFY_EMAIL").ok().filter(|s| !s.trim().is_empty()),
+            admin_locale: newsletter_locale(&std::env::var("ADMIN_NOTIFY_LOCALE").unwrap_or_default()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct NotifyPayload {
+    thread_id: String,
+    /// Who is being told.
+    to: Side,
+    /// The first message of the burst; later ones are picked up with it.
+    since: NaiveDateTime,
+}
+
+/// Schedules an email telling `to` about a new message in a DM thread,
+/// unless one is already waiting to go out, which will include it. Call it
+/// inside the transaction that stored the message. An email that is being
+/// sent right now stops short of the new one's burst (see `next_burst`).
+pub fn schedule(conn: &mut PgConnection, thread_id: &str, to: Side, sent_at: NaiveDateTime) -> QueryResult<Option<String>> {
+    let config = NotifyConfig::from_env();
+    if !config.enabled || (to == Side::Admin && config.admin_email.is_none()) {
+        return Ok(None);
+    }
+
+    // Locking the thread keeps two messages sent at once from both scheduling.
+    threads::table
+        .find(thread_id)
+        .select(threads::id)
+        .for_update()
+        .first::<String>(conn)?;
+
+    let waiting: bool = diesel::select(diesel::dsl::exists(
+        jobs_table::table
+            .filter(jobs_table::kind.eq(jobs::KIND_DM_NOTIFY))
+            .filter(jobs_table::status.eq(jobs::STATUS_PENDING))
+            .filter(jobs_table::payload.contains(serde_json::json!({ "thread_id": thread_id, "to": to }))),
+    ))
+    .get_result(conn)?;
+    if waiting {
+        return Ok(None);
+    }
+
+    let payload = serde_json::to_value(NotifyPayload {
+        thread_id: thread_id.to_string(),
+        to,
+        since: sent_at,
+    })
+    .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+    let run_at = Utc::now().naive_utc() + chrono::Duration::from_std(config.window).unwrap_or_default();
+
+    jobs::enqueue(conn, jobs::KIND_DM_NOTIFY, payload, run_at).map(Some)
+}
+
+/// Reads the reader's DM email setting, changing it first when `enabled`
+/// is given. `None` if there is no such user.
+pub fn reader_setting(conn: &mut PgConnection, user_id: &str, enabled: Option<bool>) -> QueryResult<Option<bool>> {
+    let target = users::table.filter(users::id.eq(user_id));
+    match enabled {
+        Some(enabled) => diesel::update(target)
+            .set((
+                users::dm_email_notifications.eq(enabled),
+                users::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(users::dm_email_notifications)
+            .get_result(conn)
+            .optional(),
+        None => target.select(users::dm_email_notifications).first(conn).optional(),
+    }
+}
+
+fn excerpt(content: &str) -> String {
+    let content = content.trim();
+    if content.chars().count() <= EXCERPT_CHARS {
+        return content.to_string();
+    }
+    let mut cut: String = content.chars().take(EXCERPT_CHARS - 1).collect();
+    cut.push('…');
+    cut
+}
+
+/// Link that opens the thread: the admin inbox, or the reader's messages page.
+fn thread_url(to: Side, locale: &str, thread_id: &str) -> String {
+    match to {
+        Side::Admin => format!("{}/{}/admin/inbox/{}", base_url(), locale, thread_id),
+        Side::Reader => format!("{}/{}/messages?thread={}", base_url(), locale, thread_id),
+    }
+}
+
+/// Where the next burst for the same thread and recipient starts, if a
+/// message scheduled another email while this one was running. Messages
+/// from there on are left to that email. Read after the messages: a
+/// message seen there was committed together with the job it scheduled.
+fn next_burst(conn: &mut PgConnection, payload: &NotifyPayload) -> QueryResult<Option<NaiveDateTime>> {
+    let waiting: Vec<serde_json::Value> = jobs_table::table
+        .filter(jobs_table::kind.eq(jobs::KIND_DM_NOTIFY))
+        .filter(jobs_table::status.eq(jobs::STATUS_PENDING))
+        .filter(jobs_table::payload.contains(serde_json::json!({ "thread_id": payload.thread_id, "to": payload.to })))
+        .select(jobs_table::payload)
+        .load(conn)?;
+
+    Ok(waiting
+        .into_iter()
+        .filter_map(|value| serde_json::from_value::<NotifyPayload>(value).ok())
+        .map(|next| next.since)
+        .filter(|since| *since > payload.since)
+        .min())
+}
+
+/// Queues the email for a burst. Nothing is sent if the recipient has
+/// already read the messages, has no address, or turned DM emails off.
+fn queue_notification(
+    conn: &mut PgConnection,
+    engine: &TemplateEngine,
+    payload: &NotifyPayload,
+    config: &NotifyConfig,
+) -> QueryResult<Option<String>> {
+    let owner: Option<(String, Option<String>, Option<String>, bool)> = threads::table
+        .inner_join(users::table)
+        .filter(threads::id.eq(&payload.thread_id))
+        .select((users::id, users::name, users::email, users::dm_email_notifications))
+        .first(conn)
+        .optional()?;
+    let Some((user_id, name, email, wants_email)) = owner else {
+        return Ok(None);
+    };
+
+    let (recipient, locale) = match payload.to {
+        Side::Admin => (config.admin_email.clone(), config.admin_locale),
+        Side::Reader => {
+            let locale: Option<String> = newsletter_subscriptions::table
+                .filter(newsletter_subscriptions::user_id.eq(&user_id))
+                .select(newsletter_subscriptions::locale)
+                .first(conn)
+                .optional()?;
+            let recipient = if wants_email { email } else { None };
+            (recipient, newsletter_locale(locale.as_deref().unwrap_or_default()))
+        }
+    };
+    let Some(recipient) = recipient else {
+        return Ok(None);
+    };
+
+    let mut unread: Vec<(String, NaiveDateTime)> = messages::table
+        .filter(messages::thread_id.eq(&payload.thread_id))
+        .filter(messages::role.eq(payload.to.incoming()))
+        .filter(messages::read_at.is_null())
+        .filter(messages::created_at.ge(payload.since))
+        .order(messages::created_at.asc())
+        .select((messages::content, messages::created_at))
+        .load(conn)?;
+    if let Some(next) = next_burst(conn, payload)? {
+        unread.retain(|(_, created_at)| *created_at < next);
+    }
+    if unread.is_empty() {
+        return Ok(None);
+    }
+
+    let notification = DmNotification {
+        to_admin: payload.to == Side::Admin,
+        reader_name: name,
+        messages: unread
+            .iter()
+            .map(|(content, created_at)| DmExcerpt {
+                content: excerpt(content),
+                sent_at: format_kst(*created_at, locale),
+            })
+            .collect(),
+        thread_url: thread_url(payload.to, locale, &payload.thread_id),
+    };
+
+    let content = match engine.render(&notification, locale) {
+        Ok(content) => content,
+        Err(e) => {
+            tracing::error!("Failed to render DM notification for thread {}: {}", payload.thread_id, e);
+            return Ok(None);
+        }
+    };
+
+    outbox::enqueue(conn, outbox::KIND_DM_NOTIFICATION, &OutgoingEmail::new(auth_from(), recipient, content)).map(Some)
+}
+
+pub async fn run_notify_job(state: &Arc<AppState>, job: &Job) -> Result<(), String> {
+    let payload: NotifyPayload =
+        serde_json::from_value(job.payload.clone()).map_err(|e| format!("Invalid payload: {}", e))?;
+
+    let pool = state.db.clone();
+    let engine = state.templates.clone();
+    let queued = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        queue_notification(&mut conn, &engine, &payload, &NotifyConfig::from_env())
+            .map_err(|e| format!("DB query error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)))?;
+
+    if queued.is_some() {
+        outbox::wake(state);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_messages_are_cut() {
+        assert_eq!(excerpt("  short  "), "short");
+        let long = "가".repeat(EXCERPT_CHARS + 10);
+        let cut = excerpt(&long);
+        assert_eq!(cut.chars().count(), EXCERPT_CHARS);
+        assert!(cut.ends_with('…'));
+    }
+}
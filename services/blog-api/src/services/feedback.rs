@@ -0,0 +1,113 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::pg::upsert::excluded;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use super::threads::{ROLE_ASSISTANT, ROLE_USER};
+use crate::models::NewMessageFeedback;
+use crate::schema::{message_feedback, messages, threads};
+
+pub const RATING_UP: i32 = 1;
+pub const RATING_DOWN: i32 = -1;
+
+/// Why an answer was not helpful.
+pub const REASONS: &[&str] = &["inaccurate", "irrelevant", "incomplete", "outdated", "no_sources", "other"];
+
+pub const MAX_COMMENT_CHARS: usize = 2000;
+
+#[derive(thiserror::Error, Debug)]
+pub enum FeedbackError {
+    #[error("message not found")]
+    NotFound,
+
+    #[error("DB connection error: {0}")]
+    Connection(String),
+
+    #[error(transparent)]
+    Db(#[from] diesel::result::Error),
+}
+
+/// `"up"` or `"down"`.
+pub fn parse_rating(value: &str) -> Option<i32> {
+    match value {
+        "up" => Some(RATING_UP),
+        "down" => Some(RATING_DOWN),
+        _ => None,
+    }
+}
+
+/// Slugs of the sources stored with an answer (`Message.sources`).
+fn source_slugs(sources: Option<&serde_json::Value>) -> Vec<String> {
+    sources
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.get("slug").and_then(|v| v.as_str()))
+                .map(|slug| slug.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub struct Rating {
+    pub rating: i32,
+    pub reason: Option<String>,
+    pub comment: Option<String>,
+    pub locale: String,
+}
+
+/// Records a reader's rating of one of their assistant answers, replacing
+/// an earlier rating of the same answer. Returns the feedback id.
+pub fn rate(conn: &mut PgConnection, user_id: &str, message_id: &str, rating: Rating) -> Result<String, FeedbackError> {
+    conn.transaction(|conn| {
+        let (thread_id, answered_at, sources): (String, NaiveDateTime, Option<serde_json::Value>) = messages::table
+            .inner_join(threads::table)
+            .filter(messages::id.eq(message_id))
+            .filter(messages::role.eq(ROLE_ASSISTANT))
+            .filter(threads::user_id.eq(Some(user_id)))
+            .select((messages::thread_id, messages::created_at, messages::sources))
+            .first(conn)
+            .optional()?
+            .ok_or(FeedbackError::NotFound)?;
+
+        // The question is the reader's last message before the answer.
+        let query: String = messages::table
+            .filter(messages::thread_id.eq(&thread_id))
+            .filter(messages::role.eq(ROLE_USER))
+            .filter(messages::created_at.le(answered_at))
+            .order(messages::created_at.desc())
+            .select(messages::content)
+            .first(conn)
+            .optional()?
+            .unwrap_or_default();
+
+        let row = NewMessageFeedback {
+            id: cuid2::create_id(),
+            message_id: message_id.to_string(),
+            user_id: user_id.to_string(),
+            rating: rating.rating,
+            reason: rating.reason,
+            comment: rating.comment,
+            query,
+            locale: rating.locale,
+            source_slugs: source_slugs(sources.as_ref()),
+        };
+
+        let id = diesel::insert_into(message_feedback::table)
+            .values(&row)
+            .on_conflict((message_feedback::message_id, message_feedback::user_id))
+            .do_update()
+            .set((
+                message_feedback::rating.eq(excluded(message_feedback::rating)),
+                message_feedback::reason.eq(excluded(message_feedback::reason)),
+                message_feedback::comment.eq(excluded(message_feedback::comment)),
+                message_feedback::locale.eq(excluded(message_feedback::locale)),
+                message_feedback::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .returning(message_feedback::id)
+            .get_result(conn)?;
+
+        Ok(id)
+    })
+}
@@ -0,0 +1,273 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::AppState;
+use crate::models::{Job, NewJob};
+use crate::schema::jobs;
+
+pub const STATUS_PENDING: &str = "PENDING";
+pub const STATUS_RUNNING: &str = "RUNNING";
+pub const STATUS_SUCCEEDED: &str = "SUCCEEDED";
+pub const STATUS_FAILED: &str = "FAILED";
+pub const STATUS_CANCELLED: &str = "CANCELLED";
+
+pub const KIND_NEWSLETTER_SEND: &str = "newsletter.send";
+pub const KIND_NEWSLETTER_DIGEST: &str = "newsletter.digest";
+pub const KIND_DM_NOTIFY: &str = "dm.notify";
+
+/// A RUNNING job whose lock is older than this is assumed to belong to a
+/// crashed worker and becomes claimable again. Long-running handlers call
+/// [`keep_lock`] well within it.
+const STALE_LOCK_MINUTES: i64 = 15;
+
+const MAX_BACKOFF_SECS: i64 = 60 * 60;
+
+pub fn enqueue(
+    conn: &mut PgConnection,
+    kind: &str,
+    payload: serde_json::Value,
+    run_at: NaiveDateTime,
+) -> QueryResult<String> {
+    let job = NewJob {
+        id: cuid2::create_id(),
+        kind: kind.to_string(),
+        payload,
+        run_at,
+        max_attempts: 5,
+    };
+
+    diesel::insert_into(jobs::table)
+        .values(&job)
+        .execute(conn)?;
+
+    Ok(job.id)
+}
+
+/// Moves a job that has not started yet to a new run time.
+/// Returns `false` if the job is already running, finished or cancelled.
+pub fn reschedule(conn: &mut PgConnection, job_id: &str, run_at: NaiveDateTime) -> QueryResult<bool> {
+    let updated = diesel::update(
+        jobs::table
+            .filter(jobs::id.eq(job_id))
+            .filter(jobs::status.eq(STATUS_PENDING)),
+    )
+    .set((
+        jobs::run_at.eq(run_at),
+        jobs::updated_at.eq(Utc::now().naive_utc()),
+    ))
+    .execute(conn)?;
+
+    Ok(updated > 0)
+}
+
+/// Cancels a pending or running job. A running job is not interrupted, but
+/// handlers can poll [`keep_lock`] to stop early.
+pub fn cancel(conn: &mut PgConnection, job_id: &str) -> QueryResult<bool> {
+    let updated = diesel::update(
+        jobs::table
+            .filter(jobs::id.eq(job_id))
+            .filter(jobs::status.eq_any([STATUS_PENDING, STATUS_RUNNING])),
+    )
+    .set((
+        jobs::status.eq(STATUS_CANCELLED),
+        jobs::updated_at.eq(Utc::now().naive_utc()),
+    ))
+    .execute(conn)?;
+
+    Ok(updated > 0)
+}
+
+/// Refreshes the lock of a running job so it is not taken for a crashed
+/// worker's. Returns `false` if the job was cancelled or another worker has
+/// claimed it; the handler should then stop without doing more work.
+pub fn keep_lock(conn: &mut PgConnection, job: &Job) -> QueryResult<bool> {
+    let now = Utc::now().naive_utc();
+    let updated = diesel::update(
+        jobs::table
+            .filter(jobs::id.eq(&job.id))
+            .filter(jobs::status.eq(STATUS_RUNNING))
+            .filter(jobs::locked_by.eq(&job.locked_by)),
+    )
+    .set((jobs::locked_at.eq(Some(now)), jobs::updated_at.eq(now)))
+    .execute(conn)?;
+
+    Ok(updated > 0)
+}
+
+fn claim(conn: &mut PgConnection, worker_id: &str) -> QueryResult<Option<Job>> {
+    conn.transaction(|conn| {
+        let now = Utc::now().naive_utc();
+        let stale_before = now - chrono::Duration::minutes(STALE_LOCK_MINUTES);
+
+        let job: Option<Job> = jobs::table
+            .filter(
+                jobs::status
+                    .eq(STATUS_PENDING)
+                    .and(jobs::run_at.le(now))
+                    .or(jobs::status.eq(STATUS_RUNNING).and(jobs::locked_at.lt(stale_before))),
+            )
+            .order(jobs::run_at.asc())
+            .for_update()
+            .skip_locked()
+            .first(conn)
+            .optional()?;
+
+        let Some(job) = job else {
+            return Ok(None);
+        };
+
+        diesel::update(jobs::table.filter(jobs::id.eq(&job.id)))
+            .set((
+                jobs::status.eq(STATUS_RUNNING),
+                jobs::attempts.eq(job.attempts + 1),
+                jobs::locked_at.eq(Some(now)),
+                jobs::locked_by.eq(Some(worker_id)),
+                jobs::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+
+        Ok(Some(Job {
+            status: STATUS_RUNNING.to_string(),
+            attempts: job.attempts + 1,
+            locked_at: Some(now),
+            locked_by: Some(worker_id.to_string()),
+            ..job
+        }))
+    })
+}
+
+pub(super) fn backoff(attempts: i32) -> chrono::Duration {
+    let exp = attempts.clamp(1, 12) as u32 - 1;
+    chrono::Duration::seconds((30i64 << exp).min(MAX_BACKOFF_SECS))
+}
+
+fn finish(conn: &mut PgConnection, job: &Job, outcome: Result<(), String>) -> QueryResult<()> {
+    let now = Utc::now().naive_utc();
+    // A job cancelled while running keeps its CANCELLED status, and one
+    // reclaimed by another worker is left to that worker.
+    let target = jobs::table
+        .filter(jobs::id.eq(&job.id))
+        .filter(jobs::status.eq(STATUS_RUNNING))
+        .filter(jobs::locked_by.eq(&job.locked_by));
+
+    match outcome {
+        Ok(()) => {
+            diesel::update(target)
+                .set((
+                    jobs::status.eq(STATUS_SUCCEEDED),
+                    jobs::last_error.eq::<Option<String>>(None),
+                    jobs::locked_at.eq::<Option<NaiveDateTime>>(None),
+                    jobs::locked_by.eq::<Option<String>>(None),
+                    jobs::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+        Err(e) if job.attempts >= job.max_attempts => {
+            tracing::error!("Job {} ({}) failed permanently: {}", job.id, job.kind, e);
+            diesel::update(target)
+                .set((
+                    jobs::status.eq(STATUS_FAILED),
+                    jobs::last_error.eq(Some(e)),
+                    jobs::locked_at.eq::<Option<NaiveDateTime>>(None),
+                    jobs::locked_by.eq::<Option<String>>(None),
+                    jobs::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+        Err(e) => {
+            let run_at = now + backoff(job.attempts);
+            tracing::warn!(
+                "Job {} ({}) attempt {} failed, retrying at {}: {}",
+                job.id,
+                job.kind,
+                job.attempts,
+                run_at,
+                e
+            );
+            diesel::update(target)
+                .set((
+                    jobs::status.eq(STATUS_PENDING),
+                    jobs::run_at.eq(run_at),
+                    jobs::last_error.eq(Some(e)),
+                    jobs::locked_at.eq::<Option<NaiveDateTime>>(None),
+                    jobs::locked_by.eq::<Option<String>>(None),
+                    jobs::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_job(state: &Arc<AppState>, job: &Job) -> Result<(), String> {
+    match job.kind.as_str() {
+        KIND_NEWSLETTER_SEND => super::newsletter::run_send_job(state, job).await,
+        KIND_NEWSLETTER_DIGEST => super::digest::run_digest_job(state, job).await,
+        KIND_DM_NOTIFY => super::dm_notify::run_notify_job(state, job).await,
+        other => Err(format!("Unknown job kind: {}", other)),
+    }
+}
+
+async fn tick(state: &Arc<AppState>, worker_id: &str) -> Result<bool, String> {
+    let pool = state.db.clone();
+    let worker = worker_id.to_string();
+
+    let claimed = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        claim(&mut conn, &worker).map_err(|e| format!("Job claim error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)))?;
+
+    let Some(job) = claimed else {
+        return Ok(false);
+    };
+
+    tracing::info!("Running job {} ({}), attempt {}", job.id, job.kind, job.attempts);
+    let outcome = run_job(state, &job).await;
+
+    let pool = state.db.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        finish(&mut conn, &job, outcome).map_err(|e| format!("Job finish error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)))?;
+
+    Ok(true)
+}
+
+/// Starts the background worker that polls the `Job` table.
+/// Set `JOB_WORKER_ENABLED=false` to run an API-only instance.
+pub fn spawn_worker(state: Arc<AppState>) {
+    let enabled = std::env::var("JOB_WORKER_ENABLED")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true);
+    if !enabled {
+        tracing::info!("Job worker disabled");
+        return;
+    }
+
+    let poll_interval = std::env::var("JOB_POLL_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5);
+    let worker_id = format!("{}-{}", std::process::id(), &cuid2::create_id()[..8]);
+
+    tokio::spawn(async move {
+        tracing::info!("Job worker {} started", worker_id);
+        loop {
+            match tick(&state, &worker_id).await {
+                // Drain ready jobs back-to-back before sleeping again.
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => tracing::error!("Job worker error: {}", e),
+            }
+            tokio::time::sleep(Duration::from_secs(poll_interval)).await;
+        }
+    });
+}
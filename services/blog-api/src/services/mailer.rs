@@ -0,0 +1,269 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use super::email::{SmtpConfig, SmtpMailer};
+use super::resend::ResendMailer;
+use super::templates::{EmailContent, TemplateError};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutgoingEmail {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+    pub text: Option<String>,
+}
+
+impl OutgoingEmail {
+    pub fn new(from: String, to: String, content: EmailContent) -> Self {
+        Self {
+            from,
+            to,
+            subject: content.subject,
+            html: content.html,
+            text: Some(content.text),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MailError {
+    #[error("mailer not configured: {0}")]
+    NotConfigured(String),
+
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("failed to build message: {0}")]
+    Build(String),
+
+    #[error("transport error: {0}")]
+    Transport(String),
+
+    #[error("provider rejected message ({status}): {body}")]
+    Rejected { status: u16, body: String },
+
+    #[error("capture error: {0}")]
+    Capture(String),
+
+    #[error(transparent)]
+    Template(#[from] TemplateError),
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: &OutgoingEmail) -> Result<(), MailError>;
+
+    /// Backend name, for logs.
+    fn backend(&self) -> &'static str;
+
+    /// `false` when no backend is set up and every send fails.
+    fn is_configured(&self) -> bool {
+        true
+    }
+}
+
+/// Stands in when no backend is configured, so the API still starts.
+/// Every send fails with [`MailError::NotConfigured`].
+pub struct UnconfiguredMailer {
+    reason: String,
+}
+
+impl UnconfiguredMailer {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self { reason: reason.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for UnconfiguredMailer {
+    async fn send(&self, _email: &OutgoingEmail) -> Result<(), MailError> {
+        Err(MailError::NotConfigured(self.reason.clone()))
+    }
+
+    fn backend(&self) -> &'static str {
+        "none"
+    }
+
+    fn is_configured(&self) -> bool {
+        false
+    }
+}
+
+/// The memory capture keeps only the newest messages.
+const MAX_CAPTURED: usize = 1000;
+
+/// Keeps the last [`MAX_CAPTURED`] messages in memory and, when a directory
+/// is given, also writes each one as a JSON file. Nothing leaves the machine.
+#[derive(Default)]
+pub struct CaptureMailer {
+    sent: Mutex<Vec<OutgoingEmail>>,
+    dir: Option<PathBuf>,
+}
+
+impl CaptureMailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            sent: Mutex::default(),
+            dir: Some(dir.into()),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn captured(&self) -> Vec<OutgoingEmail> {
+        self.sent.lock().map(|sent| sent.clone()).unwrap_or_default()
+    }
+
+    #[cfg(test)]
+    pub fn clear(&self) {
+        if let Ok(mut sent) = self.sent.lock() {
+            sent.clear();
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for CaptureMailer {
+    async fn send(&self, email: &OutgoingEmail) -> Result<(), MailError> {
+        if let Some(dir) = &self.dir {
+            let path = dir.join(format!(
+                "{}-{}.json",
+                chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+                cuid2::create_id()
+            ));
+            let json = serde_json::to_vec_pretty(email).map_err(|e| MailError::Capture(e.to_string()))?;
+
+            tokio::fs::create_dir_all(dir)
+                .await
+                .map_err(|e| MailError::Capture(e.to_string()))?;
+            tokio::fs::write(&path, json)
+                .await
+                .map_err(|e| MailError::Capture(e.to_string()))?;
+        }
+
+        let mut sent = self.sent.lock().map_err(|e| MailError::Capture(e.to_string()))?;
+        if sent.len() >= MAX_CAPTURED {
+            sent.remove(0);
+        }
+        sent.push(email.clone());
+
+        Ok(())
+    }
+
+    fn backend(&self) -> &'static str {
+        if self.dir.is_some() {
+            "file"
+        } else {
+            "memory"
+        }
+    }
+}
+
+/// Picks the backend from `MAIL_BACKEND` (`smtp`, `resend`, `memory` or
+/// `file`). Without it, Resend is used when `RESEND_API_KEY` is set, then
+/// SMTP when `SMTP_USER` is set. Capture is only used when asked for, so a
+/// deploy missing its mail settings fails loudly instead of dropping mail.
+pub fn from_env() -> Result<Arc<dyn Mailer>, MailError> {
+    let backend = std::env::var("MAIL_BACKEND").unwrap_or_default().to_lowercase();
+
+    let mailer: Arc<dyn Mailer> = match backend.as_str() {
+        "smtp" => Arc::new(SmtpMailer::new(SmtpConfig::from_env()?)?),
+        "resend" => Arc::new(ResendMailer::from_env()?),
+        "memory" => Arc::new(CaptureMailer::new()),
+        "file" => {
+            let dir = std::env::var("MAIL_CAPTURE_DIR").unwrap_or_else(|_| "./mail-capture".to_string());
+            Arc::new(CaptureMailer::with_dir(dir))
+        }
+        // With both credentials present, picking one would silently move
+        // mail between providers when a key is added, so make it explicit.
+        "" if std::env::var("RESEND_API_KEY").is_ok() && std::env::var("SMTP_USER").is_ok() => {
+            return Err(MailError::NotConfigured(
+                "both RESEND_API_KEY and SMTP_USER are set; choose one with MAIL_BACKEND=resend or MAIL_BACKEND=smtp"
+                    .to_string(),
+            ))
+        }
+        "" if std::env::var("RESEND_API_KEY").is_ok() => Arc::new(ResendMailer::from_env()?),
+        "" if std::env::var("SMTP_USER").is_ok() => Arc::new(SmtpMailer::new(SmtpConfig::from_env()?)?),
+        "" => {
+            return Err(MailError::NotConfigured(
+                "set MAIL_BACKEND, RESEND_API_KEY or SMTP_USER".to_string(),
+            ))
+        }
+        other => return Err(MailError::NotConfigured(format!("unknown MAIL_BACKEND: {}", other))),
+    };
+
+    tracing::info!("Mail backend: {}", mailer.backend());
+    Ok(mailer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(to: &str) -> OutgoingEmail {
+        OutgoingEmail {
+            from: "Archives <noreply@example.com>".to_string(),
+            to: to.to_string(),
+            subject: "Hello".to_string(),
+            html: "<p>Hello</p>".to_string(),
+            text: Some("Hello".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn memory_capture_records_messages() {
+        let mailer = CaptureMailer::new();
+        mailer.send(&sample("a@example.com")).await.unwrap();
+        mailer.send(&sample("b@example.com")).await.unwrap();
+
+        let sent = mailer.captured();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1].to, "b@example.com");
+        assert_eq!(sent[0].text.as_deref(), Some("Hello"));
+
+        mailer.clear();
+        assert!(mailer.captured().is_empty());
+    }
+
+    #[tokio::test]
+    async fn memory_capture_keeps_newest() {
+        let mailer = CaptureMailer::new();
+        for i in 0..MAX_CAPTURED + 2 {
+            mailer.send(&sample(&format!("{}@example.com", i))).await.unwrap();
+        }
+
+        let sent = mailer.captured();
+        assert_eq!(sent.len(), MAX_CAPTURED);
+        assert_eq!(sent[0].to, "2@example.com");
+    }
+
+    #[tokio::test]
+    async fn unconfigured_mailer_refuses_to_send() {
+        let mailer = UnconfiguredMailer::new("no backend");
+        assert!(!mailer.is_configured());
+        assert!(matches!(
+            mailer.send(&sample("a@example.com")).await,
+            Err(MailError::NotConfigured(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn file_capture_writes_json() {
+        let dir = std::env::temp_dir().join(format!("mail-capture-{}", cuid2::create_id()));
+        let mailer = CaptureMailer::with_dir(&dir);
+        mailer.send(&sample("a@example.com")).await.unwrap();
+
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(files.len(), 1);
+        let body = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+        assert!(body.contains("a@example.com"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,19 @@
+use diesel::dsl::exists;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use crate::schema::{orders, products};
+
+pub const MEMBERSHIP_CATEGORY: &str = "membership";
+
+/// Whether the user has a completed order for a membership product.
+pub fn is_paid_member(conn: &mut PgConnection, user_id: &str) -> QueryResult<bool> {
+    diesel::select(exists(
+        orders::table
+            .inner_join(products::table)
+            .filter(orders::user_id.eq(user_id))
+            .filter(orders::status.eq("COMPLETED"))
+            .filter(products::category.eq(MEMBERSHIP_CATEGORY)),
+    ))
+    .get_result(conn)
+}
@@ -1,22 +1,58 @@
 pub mod db;
 pub mod stripe;
 pub mod resend;
+pub mod mailer;
 pub mod email;
+pub mod templates;
+pub mod jobs;
+pub mod outbox;
+pub mod newsletter;
+pub mod digest;
+pub mod dm_notify;
+pub mod tracking;
+pub mod membership;
+pub mod receipts;
+pub mod threads;
+pub mod chat_guard;
+pub mod chat_history;
+pub mod answer_cache;
+pub mod admin_dm;
+pub mod chat_quota;
+pub mod chat_stream;
+pub mod feedback;
+pub mod sse;
 
 pub use db::DbPool;
 
 use std::sync::Arc;
+use tokio::sync::Notify;
+
+use chat_stream::ChatMetrics;
+use mailer::Mailer;
+use templates::TemplateEngine;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: Arc<DbPool>,
+    pub mailer: Arc<dyn Mailer>,
+    pub templates: Arc<TemplateEngine>,
+    pub outbox_wake: Arc<Notify>,
+    pub chat_metrics: Arc<ChatMetrics>,
 }
 
 impl AppState {
     pub fn new() -> Self {
         let pool = db::establish_pool();
+        let mailer = mailer::from_env().unwrap_or_else(|e| {
+            tracing::error!("Outgoing mail is disabled: {}", e);
+            Arc::new(mailer::UnconfiguredMailer::new(e.to_string())) as Arc<dyn Mailer>
+        });
         Self {
             db: Arc::new(pool),
+            mailer,
+            templates: Arc::new(TemplateEngine::from_env()),
+            outbox_wake: Arc::new(Notify::new()),
+            chat_metrics: Arc::new(ChatMetrics::default()),
         }
     }
 }
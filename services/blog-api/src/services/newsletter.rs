@@ -0,0 +1,477 @@
+use diesel::dsl::{exists, not};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::jobs;
+use super::mailer::{MailError, OutgoingEmail};
+use super::templates::{self, EmailTemplate};
+use super::tracking::TrackingConfig;
+use super::AppState;
+use crate::models::{Job, NewsletterIssue, NewsletterStatus, NewsletterSubscription};
+use crate::schema::{
+    newsletter_deliveries, newsletter_issues, newsletter_subscriptions, newsletter_suppressions,
+};
+
+pub const ISSUE_DRAFT: &str = "DRAFT";
+pub const ISSUE_SCHEDULED: &str = "SCHEDULED";
+pub const ISSUE_SENDING: &str = "SENDING";
+pub const ISSUE_SENT: &str = "SENT";
+
+pub const ISSUE_KIND_MANUAL: &str = "manual";
+pub const ISSUE_KIND_DIGEST: &str = "digest";
+
+pub const TOPICS: &[&str] = &["essays", "dev_notes", "shop_news"];
+
+pub const FREQUENCY_EVERY_POST: &str = "every_post";
+pub const FREQUENCY_WEEKLY_DIGEST: &str = "weekly_digest";
+
+pub const PAUSE_DAYS: &[i64] = &[30, 60, 90];
+
+const DELIVERY_PENDING: &str = "PENDING";
+const DELIVERY_SENT: &str = "SENT";
+const DELIVERY_FAILED: &str = "FAILED";
+
+/// How often a running send refreshes its job lock and checks whether the
+/// job was cancelled.
+const CANCEL_CHECK_EVERY: usize = 20;
+
+#[derive(Serialize, Deserialize)]
+pub struct SendIssuePayload {
+    pub issue_id: String,
+}
+
+pub fn sha256_hash(value: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(value.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn generate_token() -> String {
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+pub fn base_url() -> String {
+    std::env::var("NEXT_PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:7071".to_string())
+}
+
+pub fn newsletter_from() -> String {
+    std::env::var("NEWSLETTER_FROM")
+        .or_else(|_| std::env::var("AUTH_EMAIL_FROM"))
+        .unwrap_or_else(|_| "Archives <onboarding@resend.dev>".to_string())
+}
+
+pub fn newsletter_locale(locale: &str) -> &'static str {
+    if locale == "en" {
+        "en"
+    } else {
+        "ko"
+    }
+}
+
+pub fn unsubscribe_url(locale: &str, token: &str) -> String {
+    format!("{}/{}/newsletter/unsubscribe?token={}", base_url(), locale, token)
+}
+
+pub async fn send_newsletter_email<T: EmailTemplate>(
+    state: &AppState,
+    to: String,
+    template: &T,
+    locale: &str,
+) -> Result<(), MailError> {
+    let content = state.templates.render(template, locale)?;
+    state.mailer.send(&OutgoingEmail::new(newsletter_from(), to, content)).await
+}
+
+
+/// The default send slot: Saturday 09:00 in Korea (00:00 UTC).
+pub fn next_saturday_morning_kst(now: chrono::DateTime<chrono::Utc>) -> chrono::NaiveDateTime {
+    use chrono::{Datelike, TimeZone};
+
+    let kst = chrono::FixedOffset::east_opt(9 * 3600).expect("valid offset");
+    let local = now.with_timezone(&kst);
+    let days_ahead = (5 + 7 - local.weekday().num_days_from_monday() as i64) % 7;
+
+    let mut date = local.date_naive() + chrono::Duration::days(days_ahead);
+    let slot = |date: chrono::NaiveDate| {
+        kst.from_local_datetime(&date.and_hms_opt(9, 0, 0).unwrap_or_default())
+            .single()
+            .map(|dt| dt.naive_utc())
+            .unwrap_or_default()
+    };
+
+    if slot(date) <= now.naive_utc() {
+        date += chrono::Duration::days(7);
+    }
+    slot(date)
+}
+
+/// Resolves a subscriber from either the subscription's own unsubscribe token
+/// or the per-recipient token embedded in a newsletter issue.
+pub fn find_subscription_by_token(
+    conn: &mut PgConnection,
+    token_hash: &str,
+) -> QueryResult<Option<NewsletterSubscription>> {
+    let direct: Option<NewsletterSubscription> = newsletter_subscriptions::table
+        .filter(newsletter_subscriptions::unsubscribe_token_hash.eq(token_hash))
+        .first(conn)
+        .optional()?;
+
+    if direct.is_some() {
+        return Ok(direct);
+    }
+
+    newsletter_deliveries::table
+        .inner_join(newsletter_subscriptions::table)
+        .filter(newsletter_deliveries::token_hash.eq(token_hash))
+        .select(newsletter_subscriptions::all_columns)
+        .first(conn)
+        .optional()
+}
+
+fn send_interval() -> Duration {
+    let millis = std::env::var("NEWSLETTER_SEND_INTERVAL_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(500);
+    Duration::from_millis(millis)
+}
+
+struct Recipient {
+    subscription_id: String,
+    email: String,
+    tracking_opt_out: bool,
+}
+
+struct PendingSend {
+    issue: NewsletterIssue,
+    /// Subscribers still waiting for this issue.
+    recipients: Vec<Recipient>,
+}
+
+fn start_send(conn: &mut PgConnection, issue_id: &str) -> QueryResult<Option<PendingSend>> {
+    let issue: Option<NewsletterIssue> = newsletter_issues::table
+        .filter(newsletter_issues::id.eq(issue_id))
+        .first(conn)
+        .optional()?;
+
+    let Some(issue) = issue else {
+        return Ok(None);
+    };
+
+    if issue.status != ISSUE_SCHEDULED && issue.status != ISSUE_SENDING {
+        return Ok(None);
+    }
+
+    diesel::update(newsletter_issues::table.filter(newsletter_issues::id.eq(issue_id)))
+        .set((
+            newsletter_issues::status.eq(ISSUE_SENDING),
+            newsletter_issues::updated_at.eq(chrono::Utc::now().naive_utc()),
+        ))
+        .execute(conn)?;
+
+    let now = chrono::Utc::now().naive_utc();
+
+    // Recipients that already got this issue on a previous attempt are skipped,
+    // so retries never send duplicates.
+    let mut query = newsletter_subscriptions::table
+        .filter(newsletter_subscriptions::status.eq(NewsletterStatus::ACTIVE))
+        .filter(newsletter_subscriptions::locale.eq(&issue.locale))
+        .filter(
+            newsletter_subscriptions::paused_until
+                .is_null()
+                .or(newsletter_subscriptions::paused_until.le(now)),
+        )
+        .filter(not(exists(
+            newsletter_suppressions::table
+                .filter(newsletter_suppressions::email.eq(newsletter_subscriptions::email)),
+        )))
+        .filter(not(exists(
+            newsletter_deliveries::table
+                .filter(newsletter_deliveries::issue_id.eq(issue_id))
+                .filter(newsletter_deliveries::subscription_id.eq(newsletter_subscriptions::id))
+                .filter(newsletter_deliveries::status.eq(DELIVERY_SENT)),
+        )))
+        .order(newsletter_subscriptions::created_at.asc())
+        .select((
+            newsletter_subscriptions::id,
+            newsletter_subscriptions::email,
+            newsletter_subscriptions::tracking_opt_out,
+        ))
+        .into_boxed();
+
+    // Digests only go to weekly-digest readers; every-post readers get the
+    // posts as they come. Hand-written issues (shop news, the Saturday
+    // letter) are not part of any digest, so they go to everyone.
+    if issue.kind == ISSUE_KIND_DIGEST {
+        query = query.filter(newsletter_subscriptions::frequency.eq(FREQUENCY_WEEKLY_DIGEST));
+    }
+
+    if let Some(topic) = &issue.topic {
+        query = query.filter(newsletter_subscriptions::topics.contains(vec![topic.clone()]));
+    }
+
+    let recipients: Vec<(String, String, bool)> = query.load(conn)?;
+
+    let recipients = recipients
+        .into_iter()
+        .map(|(subscription_id, email, tracking_opt_out)| Recipient {
+            subscription_id,
+            email,
+            tracking_opt_out,
+        })
+        .collect();
+
+    Ok(Some(PendingSend { issue, recipients }))
+}
+
+fn record_delivery(
+    conn: &mut PgConnection,
+    issue_id: &str,
+    subscription_id: &str,
+    token_hash: &str,
+    tracked: bool,
+) -> QueryResult<String> {
+    diesel::insert_into(newsletter_deliveries::table)
+        .values((
+            newsletter_deliveries::id.eq(cuid2::create_id()),
+            newsletter_deliveries::issue_id.eq(issue_id),
+            newsletter_deliveries::subscription_id.eq(subscription_id),
+            newsletter_deliveries::token_hash.eq(token_hash),
+            newsletter_deliveries::status.eq(DELIVERY_PENDING),
+            newsletter_deliveries::tracked.eq(tracked),
+        ))
+        .on_conflict((newsletter_deliveries::issue_id, newsletter_deliveries::subscription_id))
+        .do_update()
+        .set((
+            newsletter_deliveries::token_hash.eq(token_hash),
+            newsletter_deliveries::status.eq(DELIVERY_PENDING),
+            newsletter_deliveries::error.eq::<Option<String>>(None),
+            newsletter_deliveries::tracked.eq(tracked),
+        ))
+        .returning(newsletter_deliveries::id)
+        .get_result(conn)
+}
+
+fn update_delivery(conn: &mut PgConnection, token_hash: &str, result: &Result<(), String>) -> QueryResult<()> {
+    let target = newsletter_deliveries::table.filter(newsletter_deliveries::token_hash.eq(token_hash));
+
+    match result {
+        Ok(()) => diesel::update(target)
+            .set((
+                newsletter_deliveries::status.eq(DELIVERY_SENT),
+                newsletter_deliveries::sent_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .execute(conn)?,
+        Err(e) => diesel::update(target)
+            .set((
+                newsletter_deliveries::status.eq(DELIVERY_FAILED),
+                newsletter_deliveries::error.eq(Some(e.as_str())),
+            ))
+            .execute(conn)?,
+    };
+
+    Ok(())
+}
+
+pub async fn run_send_job(state: &Arc<AppState>, job: &Job) -> Result<(), String> {
+    let payload: SendIssuePayload =
+        serde_json::from_value(job.payload.clone()).map_err(|e| format!("Invalid payload: {}", e))?;
+
+    let pool = state.db.clone();
+    let issue_id = payload.issue_id.clone();
+    let started = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        start_send(&mut conn, &issue_id).map_err(|e| format!("DB query error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)))?;
+
+    let Some(PendingSend { issue, recipients }) = started else {
+        tracing::info!("Issue {} is not sendable, skipping job {}", payload.issue_id, job.id);
+        return Ok(());
+    };
+
+    let locale = newsletter_locale(&issue.locale);
+    let interval = send_interval();
+    let tracking = issue.tracking_enabled.then(TrackingConfig::from_env).flatten();
+    if issue.tracking_enabled && tracking.is_none() {
+        tracing::warn!("Issue {} requests tracking but tracking is not configured", issue.id);
+    }
+    let total = recipients.len();
+    let mut failed = 0usize;
+
+    for (i, recipient) in recipients.into_iter().enumerate() {
+        if i > 0 && i % CANCEL_CHECK_EVERY == 0 {
+            let pool = state.db.clone();
+            let held_job = job.clone();
+            let held = tokio::task::spawn_blocking(move || {
+                let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+                jobs::keep_lock(&mut conn, &held_job).map_err(|e| format!("DB update error: {}", e))
+            })
+            .await
+            .unwrap_or_else(|e| Err(format!("Task error: {}", e)))?;
+
+            if !held {
+                tracing::info!(
+                    "Send of issue {} stopped after {} recipients: job cancelled or reclaimed",
+                    issue.id,
+                    i
+                );
+                return Ok(());
+            }
+        }
+
+        let token = generate_token();
+        let token_hash = sha256_hash(&token);
+
+        let tracking = tracking.as_ref().filter(|_| !recipient.tracking_opt_out);
+
+        let pool = state.db.clone();
+        let (issue_id, sub_id, hash) = (issue.id.clone(), recipient.subscription_id, token_hash.clone());
+        let tracked = tracking.is_some();
+        let delivery_id = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+            record_delivery(&mut conn, &issue_id, &sub_id, &hash, tracked)
+                .map_err(|e| format!("DB insert error: {}", e))
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("Task error: {}", e)))?;
+
+        let (body_html, pixel_url) = match tracking {
+            Some(t) => (t.rewrite_links(&issue.html, &delivery_id), Some(t.open_url(&delivery_id))),
+            None => (issue.html.clone(), None),
+        };
+
+        let template = templates::NewsletterIssue::new(
+            &issue.subject,
+            &body_html,
+            issue.text.as_deref(),
+            unsubscribe_url(locale, &token),
+            pixel_url,
+        );
+        let result = send_newsletter_email(state, recipient.email, &template, locale)
+            .await
+            .map_err(|e| e.to_string());
+        if result.is_err() {
+            failed += 1;
+        }
+
+        let pool = state.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+            update_delivery(&mut conn, &token_hash, &result).map_err(|e| format!("DB update error: {}", e))
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("Task error: {}", e)))?;
+
+        tokio::time::sleep(interval).await;
+    }
+
+    let outcome = send_outcome(failed, total, job);
+    if let SendOutcome::Retry(e) = outcome {
+        return Err(e);
+    }
+
+    let pool = state.db.clone();
+    let issue_id = issue.id.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        let now = chrono::Utc::now().naive_utc();
+
+        diesel::update(
+            newsletter_issues::table
+                .filter(newsletter_issues::id.eq(&issue_id))
+                .filter(newsletter_issues::status.eq(ISSUE_SENDING)),
+        )
+        .set((
+            newsletter_issues::status.eq(ISSUE_SENT),
+            newsletter_issues::sent_at.eq(Some(now)),
+            newsletter_issues::updated_at.eq(now),
+        ))
+        .execute(&mut conn)
+        .map_err(|e| format!("DB update error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)))?;
+
+    match outcome {
+        SendOutcome::GiveUp(e) => {
+            tracing::error!("Issue {} marked sent with failed deliveries: {}", issue.id, e);
+            Err(e)
+        }
+        _ => {
+            tracing::info!("Issue {} sent to {} recipients", issue.id, total);
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum SendOutcome {
+    Complete,
+    /// Some deliveries failed; the job runs again for those recipients.
+    Retry(String),
+    /// Deliveries still failed on the job's last attempt. The issue is
+    /// marked sent anyway so one bad address cannot hold it in `SENDING`;
+    /// the failures stay on their delivery rows.
+    GiveUp(String),
+}
+
+fn send_outcome(failed: usize, total: usize, job: &Job) -> SendOutcome {
+    if failed == 0 {
+        return SendOutcome::Complete;
+    }
+    let error = format!("{} of {} deliveries failed", failed, total);
+    if job.attempts >= job.max_attempts {
+        SendOutcome::GiveUp(error)
+    } else {
+        SendOutcome::Retry(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send_job(attempts: i32) -> Job {
+        let now = chrono::Utc::now().naive_utc();
+        Job {
+            id: "job".to_string(),
+            kind: jobs::KIND_NEWSLETTER_SEND.to_string(),
+            payload: serde_json::json!({ "issue_id": "issue" }),
+            status: jobs::STATUS_RUNNING.to_string(),
+            run_at: now,
+            attempts,
+            max_attempts: 5,
+            last_error: None,
+            locked_at: Some(now),
+            locked_by: Some("worker".to_string()),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn clean_send_completes() {
+        assert_eq!(send_outcome(0, 10, &send_job(1)), SendOutcome::Complete);
+    }
+
+    #[test]
+    fn one_recipient_that_keeps_failing_does_not_hold_the_issue() {
+        for attempt in 1..5 {
+            assert!(matches!(send_outcome(1, 10, &send_job(attempt)), SendOutcome::Retry(_)));
+        }
+        assert_eq!(
+            send_outcome(1, 10, &send_job(5)),
+            SendOutcome::GiveUp("1 of 10 deliveries failed".to_string())
+        );
+    }
+}
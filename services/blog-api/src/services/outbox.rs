@@ -0,0 +1,335 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::jobs;
+use super::mailer::OutgoingEmail;
+use super::AppState;
+use crate::models::{NewOutboxEmail, OutboxEmail};
+use crate::schema::email_outbox;
+
+pub const STATUS_PENDING: &str = "PENDING";
+pub const STATUS_SENDING: &str = "SENDING";
+pub const STATUS_SENT: &str = "SENT";
+pub const STATUS_DEAD: &str = "DEAD";
+
+pub const KIND_MAGIC_LINK: &str = "auth.magic_link";
+pub const KIND_NEWSLETTER_CONFIRM: &str = "newsletter.confirm";
+pub const KIND_PURCHASE_RECEIPT: &str = "shop.receipt";
+pub const KIND_MEMBERSHIP_WELCOME: &str = "shop.membership_welcome";
+pub const KIND_DM_NOTIFICATION: &str = "dm.notification";
+
+const MAX_ATTEMPTS: i32 = 8;
+
+/// A SENDING row older than this is assumed to belong to a crashed worker.
+const STALE_LOCK_MINUTES: i64 = 5;
+
+const CLAIM_BATCH: i64 = 10;
+
+/// A send that takes longer counts as failed. A whole batch of slow sends
+/// still finishes well inside [`STALE_LOCK_MINUTES`].
+const SEND_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Queues a message for delivery. Call it inside the transaction that
+/// writes whatever the message refers to (a token, a subscription), so the
+/// two are committed or rolled back together.
+pub fn enqueue(conn: &mut PgConnection, kind: &str, email: &OutgoingEmail) -> QueryResult<String> {
+    let row = NewOutboxEmail {
+        id: cuid2::create_id(),
+        kind: kind.to_string(),
+        sender: email.from.clone(),
+        recipient: email.to.clone(),
+        subject: email.subject.clone(),
+        html: email.html.clone(),
+        text: email.text.clone(),
+        max_attempts: MAX_ATTEMPTS,
+    };
+
+    diesel::insert_into(email_outbox::table)
+        .values(&row)
+        .execute(conn)?;
+
+    Ok(row.id)
+}
+
+/// Kinds that are never requeued: a magic link's body is cleared when it
+/// goes dead, and its token has expired by then anyway. The reader asks
+/// for a new link instead.
+const NOT_RETRIED: &[&str] = &[KIND_MAGIC_LINK];
+
+#[derive(thiserror::Error, Debug)]
+pub enum RetryError {
+    #[error("{0} messages cannot be resent")]
+    NotRetried(String),
+
+    #[error(transparent)]
+    Db(#[from] diesel::result::Error),
+}
+
+fn check_retryable(kind: &str) -> Result<(), RetryError> {
+    if NOT_RETRIED.contains(&kind) {
+        return Err(RetryError::NotRetried(kind.to_string()));
+    }
+    Ok(())
+}
+
+/// Puts a dead (or still pending) message back in the queue with a fresh
+/// attempt budget. Returns `false` if it was already sent or is in flight.
+pub fn retry(conn: &mut PgConnection, id: &str) -> Result<bool, RetryError> {
+    let kind: Option<String> = email_outbox::table
+        .find(id)
+        .select(email_outbox::kind)
+        .first(conn)
+        .optional()?;
+    let Some(kind) = kind else {
+        return Ok(false);
+    };
+    check_retryable(&kind)?;
+
+    let now = Utc::now().naive_utc();
+    let updated = diesel::update(
+        email_outbox::table
+            .filter(email_outbox::id.eq(id))
+            .filter(email_outbox::status.eq_any([STATUS_DEAD, STATUS_PENDING])),
+    )
+    .set((
+        email_outbox::status.eq(STATUS_PENDING),
+        email_outbox::attempts.eq(0),
+        email_outbox::next_attempt_at.eq(now),
+        email_outbox::updated_at.eq(now),
+    ))
+    .execute(conn)?;
+
+    Ok(updated > 0)
+}
+
+/// Requeues every dead message except the kinds that are never resent.
+pub fn retry_all_dead(conn: &mut PgConnection) -> QueryResult<usize> {
+    let now = Utc::now().naive_utc();
+    diesel::update(
+        email_outbox::table
+            .filter(email_outbox::status.eq(STATUS_DEAD))
+            .filter(email_outbox::kind.ne_all(NOT_RETRIED)),
+    )
+    .set((
+        email_outbox::status.eq(STATUS_PENDING),
+        email_outbox::attempts.eq(0),
+        email_outbox::next_attempt_at.eq(now),
+        email_outbox::updated_at.eq(now),
+    ))
+    .execute(conn)
+}
+
+fn claim(conn: &mut PgConnection) -> QueryResult<Vec<OutboxEmail>> {
+    conn.transaction(|conn| {
+        let now = Utc::now().naive_utc();
+        let stale_before = now - chrono::Duration::minutes(STALE_LOCK_MINUTES);
+
+        let rows: Vec<OutboxEmail> = email_outbox::table
+            .filter(
+                email_outbox::status
+                    .eq(STATUS_PENDING)
+                    .and(email_outbox::next_attempt_at.le(now))
+                    .or(email_outbox::status
+                        .eq(STATUS_SENDING)
+                        .and(email_outbox::locked_at.lt(stale_before))),
+            )
+            .order(email_outbox::next_attempt_at.asc())
+            .limit(CLAIM_BATCH)
+            .for_update()
+            .skip_locked()
+            .load(conn)?;
+
+        let ids: Vec<&str> = rows.iter().map(|row| row.id.as_str()).collect();
+        diesel::update(email_outbox::table.filter(email_outbox::id.eq_any(&ids)))
+            .set((
+                email_outbox::status.eq(STATUS_SENDING),
+                email_outbox::attempts.eq(email_outbox::attempts + 1),
+                email_outbox::locked_at.eq(Some(now)),
+                email_outbox::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| OutboxEmail {
+                status: STATUS_SENDING.to_string(),
+                attempts: row.attempts + 1,
+                locked_at: Some(now),
+                ..row
+            })
+            .collect())
+    })
+}
+
+fn finish(conn: &mut PgConnection, row: &OutboxEmail, outcome: Result<(), String>) -> QueryResult<()> {
+    let now = Utc::now().naive_utc();
+    let target = email_outbox::table
+        .filter(email_outbox::id.eq(&row.id))
+        .filter(email_outbox::status.eq(STATUS_SENDING));
+
+    match outcome {
+        Ok(()) => {
+            // Bodies can carry one-time links, so they are not kept once delivered.
+            diesel::update(target)
+                .set((
+                    email_outbox::status.eq(STATUS_SENT),
+                    email_outbox::html.eq(""),
+                    email_outbox::text.eq::<Option<String>>(None),
+                    email_outbox::last_error.eq::<Option<String>>(None),
+                    email_outbox::locked_at.eq::<Option<NaiveDateTime>>(None),
+                    email_outbox::sent_at.eq(Some(now)),
+                    email_outbox::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+        Err(e) if row.attempts >= row.max_attempts => {
+            tracing::error!("Outbox email {} ({}) is dead after {} attempts: {}", row.id, row.kind, row.attempts, e);
+            diesel::update(target)
+                .set((
+                    email_outbox::status.eq(STATUS_DEAD),
+                    email_outbox::last_error.eq(Some(e)),
+                    email_outbox::locked_at.eq::<Option<NaiveDateTime>>(None),
+                    email_outbox::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+            // A dead magic link is useless to retry and its body holds a
+            // login token, so it is not kept either.
+            if row.kind == KIND_MAGIC_LINK {
+                diesel::update(email_outbox::table.filter(email_outbox::id.eq(&row.id)))
+                    .set((
+                        email_outbox::html.eq(""),
+                        email_outbox::text.eq::<Option<String>>(None),
+                    ))
+                    .execute(conn)?;
+            }
+        }
+        Err(e) => {
+            let next_attempt_at = now + jobs::backoff(row.attempts);
+            tracing::warn!(
+                "Outbox email {} ({}) attempt {} failed, retrying at {}: {}",
+                row.id,
+                row.kind,
+                row.attempts,
+                next_attempt_at,
+                e
+            );
+            diesel::update(target)
+                .set((
+                    email_outbox::status.eq(STATUS_PENDING),
+                    email_outbox::next_attempt_at.eq(next_attempt_at),
+                    email_outbox::last_error.eq(Some(e)),
+                    email_outbox::locked_at.eq::<Option<NaiveDateTime>>(None),
+                    email_outbox::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn tick(state: &Arc<AppState>) -> Result<bool, String> {
+    let pool = state.db.clone();
+    let claimed = tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+        claim(&mut conn).map_err(|e| format!("Outbox claim error: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task error: {}", e)))?;
+
+    if claimed.is_empty() {
+        return Ok(false);
+    }
+
+    // Each result is saved as soon as the send returns, so a crash partway
+    // through the batch only re-sends the message that was in flight.
+    for row in claimed {
+        let email = OutgoingEmail {
+            from: row.sender.clone(),
+            to: row.recipient.clone(),
+            subject: row.subject.clone(),
+            html: row.html.clone(),
+            text: row.text.clone(),
+        };
+        let outcome = match tokio::time::timeout(SEND_TIMEOUT, state.mailer.send(&email)).await {
+            Ok(sent) => sent.map_err(|e| e.to_string()),
+            Err(_) => Err(format!("send timed out after {}s", SEND_TIMEOUT.as_secs())),
+        };
+
+        let pool = state.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| format!("DB connection error: {}", e))?;
+            finish(&mut conn, &row, outcome).map_err(|e| format!("Outbox finish error: {}", e))
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("Task error: {}", e)))?;
+    }
+
+    Ok(true)
+}
+
+/// Tells the outbox worker on this instance to look for new mail now
+/// instead of waiting for the next poll.
+pub fn wake(state: &AppState) {
+    state.outbox_wake.notify_one();
+}
+
+/// Starts the worker that delivers queued mail. Shares `JOB_WORKER_ENABLED`
+/// with the job worker; other instances pick up the queue when it is off.
+pub fn spawn_worker(state: Arc<AppState>) {
+    let enabled = std::env::var("JOB_WORKER_ENABLED")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true);
+    if !enabled {
+        tracing::info!("Outbox worker disabled");
+        return;
+    }
+    // Queued mail waits for an instance that can deliver it.
+    if !state.mailer.is_configured() {
+        tracing::warn!("Outbox worker not started: no mail backend configured");
+        return;
+    }
+
+    let poll_interval = std::env::var("OUTBOX_POLL_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10);
+
+    tokio::spawn(async move {
+        tracing::info!("Outbox worker started");
+        loop {
+            match tick(&state).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => tracing::error!("Outbox worker error: {}", e),
+            }
+            tokio::select! {
+                _ = state.outbox_wake.notified() => {}
+                _ = tokio::time::sleep(Duration::from_secs(poll_interval)) => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_magic_links_are_not_retried() {
+        assert!(matches!(
+            check_retryable(KIND_MAGIC_LINK),
+            Err(RetryError::NotRetried(kind)) if kind == KIND_MAGIC_LINK
+        ));
+    }
+
+    #[test]
+    fn other_kinds_can_be_retried() {
+        for kind in [KIND_NEWSLETTER_CONFIRM, KIND_PURCHASE_RECEIPT, KIND_MEMBERSHIP_WELCOME, KIND_DM_NOTIFICATION] {
+            assert!(check_retryable(kind).is_ok(), "{}", kind);
+        }
+    }
+}
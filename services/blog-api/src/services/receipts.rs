@@ -0,0 +1,160 @@
+use chrono::NaiveDateTime;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use super::email::auth_from;
+use super::mailer::OutgoingEmail;
+use super::membership::MEMBERSHIP_CATEGORY;
+use super::newsletter::{base_url, newsletter_locale};
+use super::outbox;
+use super::templates::{EmailContent, MembershipWelcome, PurchaseReceipt, TemplateEngine, TemplateError};
+use crate::schema::{orders, products, users};
+
+/// How the order was paid for, as shown on the receipt.
+pub enum Payment {
+    Points(i32),
+    /// Amount in the currency's minor unit, as Stripe reports it.
+    Cash { amount: i64, currency: String },
+}
+
+/// Currencies Stripe counts in whole units.
+const ZERO_DECIMAL_CURRENCIES: &[&str] = &["krw", "jpy", "vnd", "clp", "isk", "twd", "ugx"];
+
+pub fn format_cash(amount: i64, currency: &str) -> String {
+    let currency = currency.to_lowercase();
+    let (whole, cents) = if ZERO_DECIMAL_CURRENCIES.contains(&currency.as_str()) {
+        (amount, None)
+    } else {
+        (amount / 100, Some(amount.rem_euclid(100)))
+    };
+
+    let digits = whole.abs().to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    if whole < 0 {
+        grouped.insert(0, '-');
+    }
+    let number = match cents {
+        Some(cents) => format!("{}.{:02}", grouped, cents),
+        None => grouped,
+    };
+
+    match currency.as_str() {
+        "usd" => format!("${}", number),
+        "krw" => format!("₩{}", number),
+        "eur" => format!("€{}", number),
+        "jpy" => format!("¥{}", number),
+        other => format!("{} {}", number, other.to_uppercase()),
+    }
+}
+
+/// A timestamp in KST, where the shop is run from.
+pub(super) fn format_kst(created_at: NaiveDateTime, locale: &str) -> String {
+    let kst = chrono::FixedOffset::east_opt(9 * 3600).expect("valid offset");
+    let local = created_at.and_utc().with_timezone(&kst);
+    if locale == "en" {
+        local.format("%B %-d, %Y %H:%M KST").to_string()
+    } else {
+        local.format("%Y년 %-m월 %-d일 %H:%M").to_string()
+    }
+}
+
+/// Product images are usually site-relative paths.
+fn absolute_url(path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        path.to_string()
+    } else {
+        format!("{}/{}", base_url().trim_end_matches('/'), path.trim_start_matches('/'))
+    }
+}
+
+fn render_order_email(
+    engine: &TemplateEngine,
+    receipt: PurchaseReceipt,
+    membership: bool,
+    locale: &str,
+) -> Result<(&'static str, EmailContent), TemplateError> {
+    if membership {
+        let template = MembershipWelcome {
+            receipt,
+            archive_url: format!("{}/{}/newsletter", base_url(), locale),
+        };
+        Ok((outbox::KIND_MEMBERSHIP_WELCOME, engine.render(&template, locale)?))
+    } else {
+        Ok((outbox::KIND_PURCHASE_RECEIPT, engine.render(&receipt, locale)?))
+    }
+}
+
+/// Queues the receipt for a completed order, or the membership welcome for
+/// membership products, once ink points were deducted. Users without an
+/// email address are skipped, and a template problem is logged rather than
+/// failing the purchase. A point purchase calls this inside its transaction,
+/// so the points, order and receipt land together; the Stripe webhook calls
+/// it after the order commits, since the payment has already been taken.
+pub fn queue_order_email(
+    conn: &mut PgConnection,
+    engine: &TemplateEngine,
+    order_id: &str,
+    payment: Payment,
+    locale: &str,
+) -> QueryResult<Option<String>> {
+    let locale = newsletter_locale(locale);
+
+    let (created_at, product_name, product_image, category, email, remaining_points): (
+        NaiveDateTime,
+        String,
+        Option<String>,
+        String,
+        Option<String>,
+        i32,
+    ) = orders::table
+        .inner_join(products::table)
+        .inner_join(users::table)
+        .filter(orders::id.eq(order_id))
+        .select((
+            orders::created_at,
+            products::name,
+            products::image,
+            products::category,
+            users::email,
+            users::ink_points,
+        ))
+        .first(conn)?;
+
+    let Some(email) = email else {
+        tracing::info!("Order {} has no email address; skipping receipt", order_id);
+        return Ok(None);
+    };
+
+    let (points, cash) = match payment {
+        Payment::Points(points) => (Some(points), None),
+        Payment::Cash { amount, currency } => (None, Some(format_cash(amount, &currency))),
+    };
+
+    let receipt = PurchaseReceipt {
+        order_id: order_id.to_string(),
+        product_name,
+        product_image: product_image.as_deref().map(absolute_url),
+        points,
+        cash,
+        ordered_at: format_kst(created_at, locale),
+        remaining_points,
+        orders_url: format!("{}/{}/shop", base_url(), locale),
+    };
+
+    let membership = category == MEMBERSHIP_CATEGORY;
+    let (kind, content) = match render_order_email(engine, receipt, membership, locale) {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            tracing::error!("Failed to render receipt for order {}: {}", order_id, e);
+            return Ok(None);
+        }
+    };
+
+    outbox::enqueue(conn, kind, &OutgoingEmail::new(auth_from(), email, content)).map(Some)
+}
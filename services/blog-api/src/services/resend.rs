@@ -1,33 +1,59 @@
-use serde::Serialize;
-
-#[derive(Debug, Serialize)]
-pub struct EmailParams {
-    pub from: String,
-    pub to: String,
-    pub subject: String,
-    pub html: String,
+use async_trait::async_trait;
+
+use super::mailer::{MailError, Mailer, OutgoingEmail};
+
+pub struct ResendMailer {
+    client: reqwest::Client,
+    api_key: String,
+}
+
+impl ResendMailer {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_key,
+        }
+    }
+
+    pub fn from_env() -> Result<Self, MailError> {
+        let api_key = std::env::var("RESEND_API_KEY")
+            .map_err(|_| MailError::NotConfigured("RESEND_API_KEY not set".to_string()))?;
+        Ok(Self::new(api_key))
+    }
 }
 
-pub async fn send_email(params: EmailParams) -> Result<(), Box<dyn std::error::Error>> {
-    let api_key = std::env::var("RESEND_API_KEY")
-        .map_err(|_| "RESEND_API_KEY not set")?;
-    
-    let client = reqwest::Client::new();
-    let response = client
-        .post("https://api.resend.com/emails")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .json(&serde_json::json!({
-            "from": params.from,
-            "to": params.to,
-            "subject": params.subject,
-            "html": params.html,
-        }))
-        .send()
-        .await?;
-    
-    if !response.status().is_success() {
-        return Err(format!("Resend API error: {}", response.status()).into());
+#[async_trait]
+impl Mailer for ResendMailer {
+    async fn send(&self, email: &OutgoingEmail) -> Result<(), MailError> {
+        let mut body = serde_json::json!({
+            "from": email.from,
+            "to": email.to,
+            "subject": email.subject,
+            "html": email.html,
+        });
+        if let Some(text) = &email.text {
+            body["text"] = serde_json::Value::String(text.clone());
+        }
+
+        let response = self
+            .client
+            .post("https://api.resend.com/emails")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| MailError::Transport(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(MailError::Rejected { status, body });
+        }
+
+        Ok(())
+    }
+
+    fn backend(&self) -> &'static str {
+        "resend"
     }
-    
-    Ok(())
 }
@@ -0,0 +1,246 @@
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+pub const DEFAULT_EVENT: &str = "message";
+
+/// One dispatched event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The `event:` name, `"message"` when none was given.
+    pub event: String,
+    /// All `data:` lines of the event joined with `\n`.
+    pub data: String,
+    /// Set when an `id:` field was seen since the previous event; the
+    /// value is the stream's new last event ID (possibly empty).
+    pub id: Option<String>,
+    /// Reconnection time in milliseconds, when a valid `retry:` field was
+    /// seen since the previous event.
+    pub retry: Option<u64>,
+}
+
+/// Incremental `text/event-stream` parser following the WHATWG "event
+/// stream interpretation" rules. Bytes are buffered until a full line is
+/// available, so UTF-8 sequences and CRLF pairs split across chunks
+/// survive intact.
+#[derive(Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    /// Whether the start of the stream (and a possible BOM) has been seen.
+    started: bool,
+    /// The last chunk ended in `\r`; a `\n` at the start of the next one
+    /// belongs to the same line ending.
+    pending_cr: bool,
+    data: String,
+    event: String,
+    id_changed: bool,
+    retry: Option<u64>,
+    last_event_id: String,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts from the `Last-Event-ID` a client reconnected with.
+    pub fn with_last_event_id(id: &str) -> Self {
+        Self {
+            last_event_id: id.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn last_event_id(&self) -> &str {
+        &self.last_event_id
+    }
+
+    /// Feeds the next chunk and returns the events it completed. An event
+    /// still open when the stream ends is discarded, as the spec requires.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut chunk = chunk;
+        if self.pending_cr && !chunk.is_empty() {
+            self.pending_cr = false;
+            if chunk[0] == b'\n' {
+                chunk = &chunk[1..];
+            }
+        }
+        self.buffer.extend_from_slice(chunk);
+
+        if !self.started {
+            if self.buffer.len() < BOM.len() && BOM.starts_with(&self.buffer) {
+                return Vec::new();
+            }
+            if self.buffer.starts_with(BOM) {
+                self.buffer.drain(..BOM.len());
+            }
+            self.started = true;
+        }
+
+        let buffer = std::mem::take(&mut self.buffer);
+        let mut events = Vec::new();
+        let mut start = 0;
+
+        while let Some(offset) = buffer[start..].iter().position(|b| *b == b'\n' || *b == b'\r') {
+            let end = start + offset;
+            let next = match buffer[end] {
+                b'\r' if end + 1 == buffer.len() => {
+                    self.pending_cr = true;
+                    end + 1
+                }
+                b'\r' if buffer[end + 1] == b'\n' => end + 2,
+                _ => end + 1,
+            };
+
+            if let Some(event) = self.process_line(&buffer[start..end]) {
+                events.push(event);
+            }
+            start = next;
+        }
+
+        self.buffer = buffer[start..].to_vec();
+        events
+    }
+
+    fn process_line(&mut self, line: &[u8]) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line[0] == b':' {
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => {
+                self.last_event_id = value.to_string();
+                self.id_changed = true;
+            }
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(retry) = value.parse() {
+                    self.retry = Some(retry);
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = std::mem::take(&mut self.event);
+        if self.data.is_empty() {
+            // `id` and `retry` still apply; they travel with the next event.
+            return None;
+        }
+
+        let mut data = std::mem::take(&mut self.data);
+        data.pop();
+
+        Some(SseEvent {
+            event: if event.is_empty() { DEFAULT_EVENT.to_string() } else { event },
+            data,
+            id: std::mem::take(&mut self.id_changed).then(|| self.last_event_id.clone()),
+            retry: self.retry.take(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(chunks: &[&[u8]]) -> Vec<SseEvent> {
+        let mut parser = SseParser::new();
+        chunks.iter().flat_map(|chunk| parser.feed(chunk)).collect()
+    }
+
+    fn message(data: &str) -> SseEvent {
+        SseEvent {
+            event: DEFAULT_EVENT.to_string(),
+            data: data.to_string(),
+            id: None,
+            retry: None,
+        }
+    }
+
+    #[test]
+    fn joins_multi_line_data_and_keeps_fields() {
+        let events = feed_all(&[b"event: delta\nid: 7\nretry: 1500\ndata: one\ndata:two\ndata\n\n"]);
+        assert_eq!(
+            events,
+            vec![SseEvent {
+                event: "delta".to_string(),
+                data: "one\ntwo\n".to_string(),
+                id: Some("7".to_string()),
+                retry: Some(1500),
+            }]
+        );
+    }
+
+    #[test]
+    fn byte_by_byte_matches_whole_stream() {
+        let stream = "data: {\"type\":\"content\",\"content\":\"안녕하세요 👋\"}\r\n\r\nevent: done\rdata: {}\r\r";
+        let whole = feed_all(&[stream.as_bytes()]);
+        let bytes: Vec<&[u8]> = stream.as_bytes().chunks(1).collect();
+
+        assert_eq!(whole.len(), 2);
+        assert_eq!(whole[0].data, "{\"type\":\"content\",\"content\":\"안녕하세요 👋\"}");
+        assert_eq!(whole[1].event, "done");
+        assert_eq!(feed_all(&bytes), whole);
+    }
+
+    #[test]
+    fn utf8_split_across_chunks_is_not_corrupted() {
+        let text = "data: 한글\n\n".as_bytes();
+        // Split inside the first three-byte character.
+        let events = feed_all(&[&text[..7], &text[7..]]);
+        assert_eq!(events, vec![message("한글")]);
+    }
+
+    #[test]
+    fn crlf_split_across_chunks_is_one_line_ending() {
+        let events = feed_all(&[b"data: a\r", b"\n\r", b"\ndata: b\n\n"]);
+        assert_eq!(events, vec![message("a"), message("b")]);
+    }
+
+    #[test]
+    fn bom_is_stripped_even_when_split() {
+        let events = feed_all(&[b"\xEF", b"\xBB\xBFdata: x\n\n"]);
+        assert_eq!(events, vec![message("x")]);
+    }
+
+    #[test]
+    fn ignores_comments_unknown_fields_and_empty_events() {
+        let events = feed_all(&[b": keep-alive\n\nfoo: bar\nevent: ping\n\ndata:  two spaces\n\n"]);
+        assert_eq!(events, vec![message(" two spaces")]);
+    }
+
+    #[test]
+    fn id_carries_over_until_an_event_is_dispatched() {
+        let mut parser = SseParser::with_last_event_id("3");
+        assert_eq!(parser.last_event_id(), "3");
+
+        let events = parser.feed(b"id: 4\n\nid: bad\0\nretry: 1s\ndata: a\n\ndata: b\n\n");
+        assert_eq!(events[0].id.as_deref(), Some("4"));
+        assert_eq!(events[0].retry, None);
+        assert_eq!(events[1].id, None);
+        assert_eq!(parser.last_event_id(), "4");
+    }
+
+    #[test]
+    fn incomplete_event_waits_for_blank_line() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"data: partial\n").is_empty());
+        assert!(parser.feed(b"data: more").is_empty());
+        assert_eq!(parser.feed(b"\n\n"), vec![message("partial\nmore")]);
+    }
+}
@@ -17,6 +17,8 @@ pub struct CreateCheckoutSessionParams {
     pub success_url: String,
     pub cancel_url: String,
     pub is_subscription: bool,
+    /// Stored in the session metadata so the receipt matches the shop language.
+    pub locale: String,
 }
 
 pub async fn create_checkout_session(params: CreateCheckoutSessionParams) -> Result<String, anyhow::Error> {
@@ -38,6 +40,7 @@ pub async fn create_checkout_session(params: CreateCheckoutSessionParams) -> Res
     let mut metadata: HashMap<String, String> = HashMap::new();
     metadata.insert("userId".to_string(), params.user_id);
     metadata.insert("productId".to_string(), params.product_id);
+    metadata.insert("locale".to_string(), params.locale);
     session_params.metadata = Some(metadata);
 
     let recurring = if params.is_subscription {
@@ -0,0 +1,624 @@
+use minijinja::{context, Environment, Value};
+use serde::Serialize;
+use std::path::PathBuf;
+
+pub struct EmailContent {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TemplateError {
+    #[error("unknown template: {0}")]
+    Unknown(String),
+
+    #[error("template error: {0}")]
+    Render(#[from] minijinja::Error),
+}
+
+/// Compiled-in copies of `templates/email`, used for any file missing from
+/// `EMAIL_TEMPLATE_DIR` so the binary works on its own.
+const EMBEDDED: &[(&str, &str)] = &[
+    ("layout.html", include_str!("../../templates/email/layout.html")),
+    ("fragment.html", include_str!("../../templates/email/fragment.html")),
+    ("macros.html", include_str!("../../templates/email/macros.html")),
+    ("magic_link.ko.html", include_str!("../../templates/email/magic_link.ko.html")),
+    ("magic_link.en.html", include_str!("../../templates/email/magic_link.en.html")),
+    ("newsletter_confirm.ko.html", include_str!("../../templates/email/newsletter_confirm.ko.html")),
+    ("newsletter_confirm.en.html", include_str!("../../templates/email/newsletter_confirm.en.html")),
+    ("newsletter_welcome.ko.html", include_str!("../../templates/email/newsletter_welcome.ko.html")),
+    ("newsletter_welcome.en.html", include_str!("../../templates/email/newsletter_welcome.en.html")),
+    ("newsletter_goodbye.ko.html", include_str!("../../templates/email/newsletter_goodbye.ko.html")),
+    ("newsletter_goodbye.en.html", include_str!("../../templates/email/newsletter_goodbye.en.html")),
+    ("newsletter_issue.ko.html", include_str!("../../templates/email/newsletter_issue.ko.html")),
+    ("newsletter_issue.en.html", include_str!("../../templates/email/newsletter_issue.en.html")),
+    ("newsletter_issue.ko.txt", include_str!("../../templates/email/newsletter_issue.ko.txt")),
+    ("newsletter_issue.en.txt", include_str!("../../templates/email/newsletter_issue.en.txt")),
+    ("newsletter_digest.ko.html", include_str!("../../templates/email/newsletter_digest.ko.html")),
+    ("newsletter_digest.en.html", include_str!("../../templates/email/newsletter_digest.en.html")),
+    ("newsletter_archive.ko.html", include_str!("../../templates/email/newsletter_archive.ko.html")),
+    ("newsletter_archive.en.html", include_str!("../../templates/email/newsletter_archive.en.html")),
+    ("receipt.ko.html", include_str!("../../templates/email/receipt.ko.html")),
+    ("receipt.en.html", include_str!("../../templates/email/receipt.en.html")),
+    ("receipt.ko.txt", include_str!("../../templates/email/receipt.ko.txt")),
+    ("receipt.en.txt", include_str!("../../templates/email/receipt.en.txt")),
+    ("purchase_receipt.ko.html", include_str!("../../templates/email/purchase_receipt.ko.html")),
+    ("purchase_receipt.en.html", include_str!("../../templates/email/purchase_receipt.en.html")),
+    ("purchase_receipt.ko.txt", include_str!("../../templates/email/purchase_receipt.ko.txt")),
+    ("purchase_receipt.en.txt", include_str!("../../templates/email/purchase_receipt.en.txt")),
+    ("membership_welcome.ko.html", include_str!("../../templates/email/membership_welcome.ko.html")),
+    ("membership_welcome.en.html", include_str!("../../templates/email/membership_welcome.en.html")),
+    ("membership_welcome.ko.txt", include_str!("../../templates/email/membership_welcome.ko.txt")),
+    ("membership_welcome.en.txt", include_str!("../../templates/email/membership_welcome.en.txt")),
+    ("dm_notification.ko.html", include_str!("../../templates/email/dm_notification.ko.html")),
+    ("dm_notification.en.html", include_str!("../../templates/email/dm_notification.en.html")),
+    ("dm_notification.ko.txt", include_str!("../../templates/email/dm_notification.ko.txt")),
+    ("dm_notification.en.txt", include_str!("../../templates/email/dm_notification.en.txt")),
+];
+
+/// A typed context for one template. Each template lives in
+/// `<NAME>.<locale>.html`, with an optional `<NAME>.<locale>.txt` for the
+/// plain-text part; without one the text is derived from the HTML.
+pub trait EmailTemplate: Serialize {
+    const NAME: &'static str;
+
+    /// Placeholder data for the admin preview.
+    fn sample() -> Self;
+}
+
+#[derive(Serialize)]
+pub struct MagicLink {
+    pub url: String,
+}
+
+impl EmailTemplate for MagicLink {
+    const NAME: &'static str = "magic_link";
+
+    fn sample() -> Self {
+        Self {
+            url: "https://example.com/api/auth/callback/email?token=sample".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct NewsletterConfirm {
+    pub confirm_url: String,
+    pub unsubscribe_url: String,
+}
+
+impl EmailTemplate for NewsletterConfirm {
+    const NAME: &'static str = "newsletter_confirm";
+
+    fn sample() -> Self {
+        Self {
+            confirm_url: "https://example.com/newsletter/confirm?token=sample".to_string(),
+            unsubscribe_url: "https://example.com/newsletter/unsubscribe?token=sample".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct NewsletterWelcome {
+    pub archive_url: String,
+    pub unsubscribe_url: String,
+}
+
+impl EmailTemplate for NewsletterWelcome {
+    const NAME: &'static str = "newsletter_welcome";
+
+    fn sample() -> Self {
+        Self {
+            archive_url: "https://example.com".to_string(),
+            unsubscribe_url: "https://example.com/newsletter/unsubscribe?token=sample".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct NewsletterGoodbye {
+    pub resubscribe_url: String,
+}
+
+impl EmailTemplate for NewsletterGoodbye {
+    const NAME: &'static str = "newsletter_goodbye";
+
+    fn sample() -> Self {
+        Self {
+            resubscribe_url: "https://example.com/newsletter".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct NewsletterIssue {
+    pub subject: String,
+    /// Trusted admin-authored HTML, inserted without escaping.
+    pub body_html: String,
+    pub body_text: String,
+    pub unsubscribe_url: String,
+    pub open_pixel_url: Option<String>,
+}
+
+impl NewsletterIssue {
+    /// Uses the issue's own text body when it has one.
+    pub fn new(
+        subject: &str,
+        body_html: &str,
+        body_text: Option<&str>,
+        unsubscribe_url: String,
+        open_pixel_url: Option<String>,
+    ) -> Self {
+        Self {
+            subject: subject.to_string(),
+            body_html: body_html.to_string(),
+            body_text: body_text
+                .map(|t| t.trim().to_string())
+                .unwrap_or_else(|| html_to_text(body_html)),
+            unsubscribe_url,
+            open_pixel_url,
+        }
+    }
+}
+
+impl EmailTemplate for NewsletterIssue {
+    const NAME: &'static str = "newsletter_issue";
+
+    fn sample() -> Self {
+        Self::new(
+            "Sample issue",
+            "<p>This is a sample issue with a <a href=\"https://example.com\">link</a>.</p>",
+            None,
+            "https://example.com/newsletter/unsubscribe?token=sample".to_string(),
+            None,
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct DigestPost {
+    pub title: String,
+    pub excerpt: String,
+    pub url: String,
+}
+
+/// Renders to an HTML fragment that is stored as an issue body and wrapped
+/// by [`NewsletterIssue`] at send time.
+#[derive(Serialize)]
+pub struct NewsletterDigest {
+    pub posts: Vec<DigestPost>,
+}
+
+impl EmailTemplate for NewsletterDigest {
+    const NAME: &'static str = "newsletter_digest";
+
+    fn sample() -> Self {
+        Self {
+            posts: vec![
+                DigestPost {
+                    title: "A sample essay".to_string(),
+                    excerpt: "The first lines of the post appear here.".to_string(),
+                    url: "https://example.com/catalog/sample-essay".to_string(),
+                },
+                DigestPost {
+                    title: "Another entry".to_string(),
+                    excerpt: "Each new post gets a title, an excerpt and a link.".to_string(),
+                    url: "https://example.com/catalog/another-entry".to_string(),
+                },
+            ],
+        }
+    }
+}
+
+/// Public web copy of a sent issue: the same card, without the
+/// per-recipient footer.
+#[derive(Serialize)]
+pub struct NewsletterArchivePage {
+    pub subject: String,
+    pub body_html: String,
+    pub subscribe_url: String,
+}
+
+impl EmailTemplate for NewsletterArchivePage {
+    const NAME: &'static str = "newsletter_archive";
+
+    fn sample() -> Self {
+        Self {
+            subject: "Sample issue".to_string(),
+            body_html: "<p>This is how a sent issue looks in the archive.</p>".to_string(),
+            subscribe_url: "https://example.com/newsletter".to_string(),
+        }
+    }
+}
+
+/// Order confirmation. Exactly one of `points` (ink-point purchase) and
+/// `cash` (card purchase, already formatted) is set.
+#[derive(Serialize)]
+pub struct PurchaseReceipt {
+    pub order_id: String,
+    pub product_name: String,
+    pub product_image: Option<String>,
+    pub points: Option<i32>,
+    pub cash: Option<String>,
+    pub ordered_at: String,
+    pub remaining_points: i32,
+    pub orders_url: String,
+}
+
+impl EmailTemplate for PurchaseReceipt {
+    const NAME: &'static str = "purchase_receipt";
+
+    fn sample() -> Self {
+        Self {
+            order_id: "clsample0000000000000000".to_string(),
+            product_name: "Midnight Bookmark".to_string(),
+            product_image: Some("https://example.com/images/bookmark.png".to_string()),
+            points: Some(120),
+            cash: None,
+            ordered_at: "2026-10-19 21:30".to_string(),
+            remaining_points: 380,
+            orders_url: "https://example.com/shop".to_string(),
+        }
+    }
+}
+
+/// Sent instead of the plain receipt for membership purchases; the receipt
+/// is included at the bottom.
+#[derive(Serialize)]
+pub struct MembershipWelcome {
+    #[serde(flatten)]
+    pub receipt: PurchaseReceipt,
+    pub archive_url: String,
+}
+
+impl EmailTemplate for MembershipWelcome {
+    const NAME: &'static str = "membership_welcome";
+
+    fn sample() -> Self {
+        Self {
+            receipt: PurchaseReceipt {
+                product_name: "Archive Membership".to_string(),
+                points: None,
+                cash: Some("$9.00".to_string()),
+                ..PurchaseReceipt::sample()
+            },
+            archive_url: "https://example.com/newsletter".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct DmExcerpt {
+    pub content: String,
+    pub sent_at: String,
+}
+
+/// New admin DM activity, one email per burst. `to_admin` picks the
+/// direction: a reader's messages for the admin, or the admin's replies
+/// for the reader.
+#[derive(Serialize)]
+pub struct DmNotification {
+    pub to_admin: bool,
+    pub reader_name: Option<String>,
+    pub messages: Vec<DmExcerpt>,
+    pub thread_url: String,
+}
+
+impl EmailTemplate for DmNotification {
+    const NAME: &'static str = "dm_notification";
+
+    fn sample() -> Self {
+        Self {
+            to_admin: true,
+            reader_name: Some("Jiwoo".to_string()),
+            messages: vec![
+                DmExcerpt {
+                    content: "Hello! I enjoyed the essay on night libraries.".to_string(),
+                    sent_at: "2026-10-19 21:30".to_string(),
+                },
+                DmExcerpt {
+                    content: "Is there a reading list for it?".to_string(),
+                    sent_at: "2026-10-19 21:32".to_string(),
+                },
+            ],
+            thread_url: "https://example.com/admin/inbox/clsample0000000000000000".to_string(),
+        }
+    }
+}
+
+pub const TEMPLATE_NAMES: &[&str] = &[
+    MagicLink::NAME,
+    NewsletterConfirm::NAME,
+    NewsletterWelcome::NAME,
+    NewsletterGoodbye::NAME,
+    NewsletterIssue::NAME,
+    NewsletterDigest::NAME,
+    NewsletterArchivePage::NAME,
+    PurchaseReceipt::NAME,
+    MembershipWelcome::NAME,
+    DmNotification::NAME,
+];
+
+fn brand(locale: &str) -> &'static str {
+    if locale == "ko" {
+        "심야 서고"
+    } else {
+        "Midnight Archives"
+    }
+}
+
+pub struct TemplateEngine {
+    env: Environment<'static>,
+}
+
+impl TemplateEngine {
+    /// Files in `dir` take precedence over the compiled-in templates.
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        let mut env = Environment::new();
+
+        env.set_loader(move |name| {
+            if let Some(dir) = &dir {
+                let path = dir.join(name);
+                if path.is_file() {
+                    return std::fs::read_to_string(&path).map(Some).map_err(|e| {
+                        minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, "failed to read template")
+                            .with_source(e)
+                    });
+                }
+            }
+
+            Ok(EMBEDDED
+                .iter()
+                .find(|(embedded, _)| *embedded == name)
+                .map(|(_, source)| source.to_string()))
+        });
+
+        Self { env }
+    }
+
+    /// Reads templates from `EMAIL_TEMPLATE_DIR` when set. Templates are
+    /// cached after first use, so edits there apply on the next restart.
+    pub fn from_env() -> Self {
+        let dir = std::env::var("EMAIL_TEMPLATE_DIR").ok().filter(|d| !d.is_empty()).map(PathBuf::from);
+        if let Some(dir) = &dir {
+            tracing::info!("Email templates: {} (falling back to built-in)", dir.display());
+        }
+        Self::new(dir)
+    }
+
+    pub fn render<T: EmailTemplate>(&self, data: &T, locale: &str) -> Result<EmailContent, TemplateError> {
+        let locale = if locale == "en" { "en" } else { "ko" };
+        let ctx = context! {
+            locale => locale,
+            lang => locale,
+            brand => brand(locale),
+            ..Value::from_serialize(data)
+        };
+
+        let template = self.env.get_template(&format!("{}.{}.html", T::NAME, locale))?;
+        let mut captured = template.render_captured(&ctx)?;
+        let subject = captured.with_state_mut(|state| state.render_block("subject"))?;
+        let subject = decode_entities(subject.trim());
+        let html = captured.into_output();
+
+        let text = match self.env.get_template(&format!("{}.{}.txt", T::NAME, locale)) {
+            Ok(text_template) => text_template.render(&ctx)?.trim().to_string() + "\n",
+            Err(e) if e.kind() == minijinja::ErrorKind::TemplateNotFound => html_to_text(&html) + "\n",
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(EmailContent { subject, html, text })
+    }
+
+    /// Renders any template by name with its sample data.
+    pub fn render_sample(&self, name: &str, locale: &str) -> Result<EmailContent, TemplateError> {
+        match name {
+            MagicLink::NAME => self.render(&MagicLink::sample(), locale),
+            NewsletterConfirm::NAME => self.render(&NewsletterConfirm::sample(), locale),
+            NewsletterWelcome::NAME => self.render(&NewsletterWelcome::sample(), locale),
+            NewsletterGoodbye::NAME => self.render(&NewsletterGoodbye::sample(), locale),
+            NewsletterIssue::NAME => self.render(&NewsletterIssue::sample(), locale),
+            NewsletterDigest::NAME => self.render(&NewsletterDigest::sample(), locale),
+            NewsletterArchivePage::NAME => self.render(&NewsletterArchivePage::sample(), locale),
+            PurchaseReceipt::NAME => self.render(&PurchaseReceipt::sample(), locale),
+            MembershipWelcome::NAME => self.render(&MembershipWelcome::sample(), locale),
+            DmNotification::NAME => self.render(&DmNotification::sample(), locale),
+            other => Err(TemplateError::Unknown(other.to_string())),
+        }
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&rarr;", "→")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&#x2f;", "/")
+        .replace("&amp;", "&")
+}
+
+/// Plain-text rendering of an HTML document or fragment for the text/plain
+/// part. Link targets are kept in parentheses after the link text.
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut tag = String::new();
+    let mut in_tag = false;
+    let mut skip_until: Option<&'static str> = None;
+    let mut link: Option<(String, usize)> = None;
+
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                tag.clear();
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                let closing = tag.starts_with('/');
+                let name = tag
+                    .trim_start_matches('/')
+                    .split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or("")
+                    .to_ascii_lowercase();
+
+                if let Some(end) = skip_until {
+                    if closing && name == end {
+                        skip_until = None;
+                    }
+                    continue;
+                }
+
+                match name.as_str() {
+                    "head" | "style" | "script" | "title" if !closing => {
+                        skip_until = Some(match name.as_str() {
+                            "head" => "head",
+                            "style" => "style",
+                            "script" => "script",
+                            _ => "title",
+                        });
+                    }
+                    "a" if !closing => {
+                        link = tag
+                            .split_once("href=\"")
+                            .and_then(|(_, rest)| rest.split_once('"'))
+                            .map(|(href, _)| (decode_entities(href), text.len()));
+                    }
+                    "a" => {
+                        if let Some((href, start)) = link.take() {
+                            let label = text[start..].trim().to_string();
+                            if !href.is_empty() && href != "#" && label != href {
+                                text.push_str(&format!(" ({})", href));
+                            }
+                        }
+                    }
+                    "br" | "p" | "div" | "h1" | "h2" | "h3" | "li" | "tr" | "hr" => text.push('\n'),
+                    _ => {}
+                }
+            }
+            _ if in_tag => tag.push(c),
+            _ if skip_until.is_some() => {}
+            _ => text.push(c),
+        }
+    }
+
+    let text = decode_entities(&text);
+
+    let mut out = String::new();
+    let mut blank = 0;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            blank += 1;
+            if blank > 1 {
+                continue;
+            }
+        } else {
+            blank = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dm_from(reader_name: &str, content: &str) -> DmNotification {
+        DmNotification {
+            to_admin: true,
+            reader_name: Some(reader_name.to_string()),
+            messages: vec![DmExcerpt {
+                content: content.to_string(),
+                sent_at: "2026-10-19 21:30".to_string(),
+            }],
+            thread_url: "https://example.com/admin/inbox/t1".to_string(),
+        }
+    }
+
+    #[test]
+    fn user_fields_are_escaped_in_html() {
+        let engine = TemplateEngine::new(None);
+        let email = engine
+            .render(&dm_from("<script>alert(1)</script>", "Tom & Jerry <b>hi</b>"), "en")
+            .unwrap();
+
+        assert!(!email.html.contains("<script>"), "{}", email.html);
+        assert!(email.html.contains("&lt;script&gt;alert(1)&lt;&#x2f;script&gt;"), "{}", email.html);
+        assert!(email.html.contains("Tom &amp; Jerry &lt;b&gt;hi&lt;&#x2f;b&gt;"), "{}", email.html);
+        // The subject and the .txt part are plain text, so they read as typed.
+        assert_eq!(email.subject, "New message from <script>alert(1)</script>");
+        assert!(email.text.contains("Tom & Jerry <b>hi</b>"), "{}", email.text);
+    }
+
+    #[test]
+    fn text_part_is_derived_from_html() {
+        let engine = TemplateEngine::new(None);
+        let email = engine
+            .render(&MagicLink { url: "https://example.com/cb?token=t&email=a%40b.c".to_string() }, "en")
+            .unwrap();
+
+        assert!(!email.text.contains('<'), "{}", email.text);
+        assert!(!email.text.contains("&#x"), "{}", email.text);
+        assert!(email.text.starts_with("Midnight Archives\n\nHello, dear visitor.\n\nClick the button"), "{}", email.text);
+        assert!(email.text.contains("Sign In (https://example.com/cb?token=t&email=a%40b.c)"), "{}", email.text);
+        assert!(email.text.contains("If you didn't request this"), "{}", email.text);
+        assert!(email.text.ends_with('\n'));
+    }
+
+    #[test]
+    fn unknown_locale_falls_back_to_korean() {
+        let engine = TemplateEngine::new(None);
+        let sample = MagicLink::sample();
+        let fallback = engine.render(&sample, "fr").unwrap();
+        let korean = engine.render(&sample, "ko").unwrap();
+
+        assert_eq!(fallback.subject, korean.subject);
+        assert_eq!(fallback.html, korean.html);
+        assert!(fallback.html.contains("<html lang=\"ko\">"));
+    }
+
+    #[test]
+    fn txt_file_overrides_generated_text() {
+        let dir = std::env::temp_dir().join(format!("email-templates-{}", cuid2::create_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("magic_link.en.txt"), "Sign in: {{ url }}\n\n").unwrap();
+
+        let engine = TemplateEngine::new(Some(dir.clone()));
+        let email = engine
+            .render(&MagicLink { url: "https://example.com/cb?token=t&email=a".to_string() }, "en")
+            .unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        // Text templates are not HTML-escaped.
+        assert_eq!(email.text, "Sign in: https://example.com/cb?token=t&email=a\n");
+        assert_eq!(email.subject, "Sign in to Midnight Archives");
+    }
+
+    #[test]
+    fn html_to_text_keeps_links_and_breaks() {
+        let html = concat!(
+            "<html><head><title>Skipped</title><style>p { color: red; }</style></head><body>",
+            "<h1>Title</h1><p>One&nbsp;&amp;&nbsp;two</p><br><br><br>",
+            "<p><a href=\"https://example.com/?a=1&amp;b=2\">Read more</a> ",
+            "<a href=\"https://example.com/\">https://example.com/</a> <a href=\"#\">Top</a></p>",
+            "<script>alert(1)</script></body></html>",
+        );
+
+        assert_eq!(
+            html_to_text(html),
+            "Title\n\nOne & two\n\nRead more (https://example.com/?a=1&b=2) https://example.com/ Top"
+        );
+    }
+
+    #[test]
+    fn decode_entities_decodes_once() {
+        assert_eq!(decode_entities("&lt;a&gt; &amp;lt; &#x27;&#39; &#x2f; &quot;&rarr;"), "<a> &lt; '' / \"→");
+    }
+}
@@ -0,0 +1,160 @@
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use crate::models::{NewMessage, NewThread};
+use crate::schema::{messages, threads};
+
+/// Title of the per-user thread that holds messages to the site admin.
+/// It is not an assistant conversation and is kept out of chat history.
+pub const ADMIN_DM_THREAD_TITLE: &str = "__ADMIN_DM__";
+
+pub const ROLE_USER: &str = "user";
+pub const ROLE_ASSISTANT: &str = "assistant";
+/// Replies from the site admin in an admin DM thread.
+pub const ROLE_ADMIN: &str = "admin";
+
+pub const MAX_TITLE_CHARS: usize = 60;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ThreadError {
+    #[error("thread not found")]
+    NotFound,
+
+    #[error("DB connection error: {0}")]
+    Connection(String),
+
+    #[error(transparent)]
+    Db(#[from] diesel::result::Error),
+}
+
+/// Returns the thread if it belongs to `user_id` and is a chat thread.
+pub fn owned_chat_thread(conn: &mut PgConnection, user_id: &str, thread_id: &str) -> Result<String, ThreadError> {
+    threads::table
+        .filter(threads::id.eq(thread_id))
+        .filter(threads::user_id.eq(Some(user_id)))
+        .filter(threads::title.is_null().or(threads::title.ne(ADMIN_DM_THREAD_TITLE)))
+        .select(threads::id)
+        .first(conn)
+        .optional()?
+        .ok_or(ThreadError::NotFound)
+}
+
+/// A thread title from its first question: whitespace collapsed and cut
+/// at a word boundary when possible.
+pub fn title_from_question(query: &str) -> String {
+    let text = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= MAX_TITLE_CHARS {
+        return text;
+    }
+
+    let cut: String = text.chars().take(MAX_TITLE_CHARS - 1).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > cut.len() / 2 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches([',', '.', ' ']))
+}
+
+pub(super) fn touch(conn: &mut PgConnection, thread_id: &str) -> QueryResult<()> {
+    diesel::update(threads::table.filter(threads::id.eq(thread_id)))
+        .set(threads::updated_at.eq(chrono::Utc::now().naive_utc()))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Records a user question, continuing `thread_id` or starting a new
+/// thread. Returns the thread id.
+pub fn start_turn(
+    conn: &mut PgConnection,
+    user_id: &str,
+    thread_id: Option<&str>,
+    query: &str,
+) -> Result<String, ThreadError> {
+    conn.transaction(|conn| {
+        let thread_id = match thread_id {
+            Some(id) => owned_chat_thread(conn, user_id, id)?,
+            None => {
+                let thread = NewThread {
+                    id: cuid2::create_id(),
+                    title: Some(title_from_question(query)),
+                    user_id: Some(user_id.to_string()),
+                };
+                diesel::insert_into(threads::table)
+                    .values(&thread)
+                    .execute(conn)?;
+                thread.id
+            }
+        };
+
+        diesel::insert_into(messages::table)
+            .values(&NewMessage {
+                id: cuid2::create_id(),
+                thread_id: thread_id.clone(),
+                role: ROLE_USER.to_string(),
+                content: query.to_string(),
+                sources: None,
+                incomplete: false,
+            })
+            .execute(conn)?;
+        touch(conn, &thread_id)?;
+
+        Ok(thread_id)
+    })
+}
+
+/// Stores the assistant's answer once the stream has ended, under the id
+/// announced to the client when the stream started. `incomplete` marks an
+/// answer that was cut off before the RAG service finished it.
+pub fn finish_turn(
+    conn: &mut PgConnection,
+    thread_id: &str,
+    message_id: &str,
+    content: &str,
+    sources: Option<serde_json::Value>,
+    incomplete: bool,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        diesel::insert_into(messages::table)
+            .values(&NewMessage {
+                id: message_id.to_string(),
+                thread_id: thread_id.to_string(),
+                role: ROLE_ASSISTANT.to_string(),
+                content: content.to_string(),
+                sources,
+                incomplete,
+            })
+            .execute(conn)?;
+        touch(conn, thread_id)?;
+        Ok(())
+    })
+}
+
+/// Takes back a question that got no answer at all: removes the thread's
+/// latest message when it is that question, and the thread itself when
+/// nothing else is left in it.
+pub fn abandon_turn(conn: &mut PgConnection, thread_id: &str) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let latest: Option<(String, String)> = messages::table
+            .filter(messages::thread_id.eq(thread_id))
+            .order(messages::created_at.desc())
+            .select((messages::id, messages::role))
+            .first(conn)
+            .optional()?;
+
+        match latest {
+            Some((id, role)) if role == ROLE_USER => {
+                diesel::delete(messages::table.find(id)).execute(conn)?;
+            }
+            _ => return Ok(()),
+        }
+
+        let remaining: i64 = messages::table
+            .filter(messages::thread_id.eq(thread_id))
+            .count()
+            .get_result(conn)?;
+        if remaining == 0 {
+            diesel::delete(threads::table.find(thread_id)).execute(conn)?;
+        }
+        Ok(())
+    })
+}
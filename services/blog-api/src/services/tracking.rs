@@ -0,0 +1,285 @@
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::schema::{newsletter_deliveries, newsletter_events, newsletter_subscriptions};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const EVENT_OPEN: &str = "open";
+pub const EVENT_CLICK: &str = "click";
+
+/// A transparent 1x1 GIF served for open tracking.
+pub const PIXEL_GIF: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+/// Signing secret and public API origin used to build tracking URLs.
+/// Tracking is disabled unless both are configured.
+pub struct TrackingConfig {
+    secret: String,
+    api_base_url: String,
+}
+
+impl TrackingConfig {
+    pub fn from_env() -> Option<Self> {
+        let secret = std::env::var("NEWSLETTER_TRACKING_SECRET").ok().filter(|s| !s.is_empty())?;
+        let api_base_url = std::env::var("API_PUBLIC_URL").ok().filter(|s| !s.is_empty())?;
+
+        Some(Self {
+            secret,
+            api_base_url: api_base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    fn mac(&self, parts: &[&str]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes()).expect("HMAC accepts any key length");
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                mac.update(b"\n");
+            }
+            mac.update(part.as_bytes());
+        }
+        mac
+    }
+
+    pub fn sign(&self, parts: &[&str]) -> String {
+        hex::encode(self.mac(parts).finalize().into_bytes())
+    }
+
+    pub fn verify(&self, parts: &[&str], signature: &str) -> bool {
+        match hex::decode(signature) {
+            Ok(sig) => self.mac(parts).verify_slice(&sig).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    pub fn open_url(&self, delivery_id: &str) -> String {
+        format!(
+            "{}/api/newsletter/t/open?d={}&s={}",
+            self.api_base_url,
+            delivery_id,
+            self.sign(&[EVENT_OPEN, delivery_id])
+        )
+    }
+
+    pub fn click_url(&self, delivery_id: &str, url: &str) -> String {
+        format!(
+            "{}/api/newsletter/t/click?d={}&u={}&s={}",
+            self.api_base_url,
+            delivery_id,
+            urlencoding::encode(url),
+            self.sign(&[EVENT_CLICK, delivery_id, url])
+        )
+    }
+
+    /// Rewrites every absolute `href="http..."` in an HTML fragment to go
+    /// through the signed click redirect.
+    pub fn rewrite_links(&self, html: &str, delivery_id: &str) -> String {
+        const HREF: &str = "href=\"";
+
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+
+        while let Some(start) = rest.find(HREF) {
+            let value_start = start + HREF.len();
+            out.push_str(&rest[..value_start]);
+            rest = &rest[value_start..];
+
+            let Some(end) = rest.find('"') else {
+                break;
+            };
+            let href = &rest[..end];
+            let url = href.replace("&amp;", "&");
+
+            if url.starts_with("https://") || url.starts_with("http://") {
+                out.push_str(&self.click_url(delivery_id, &url).replace('&', "&amp;"));
+            } else {
+                out.push_str(href);
+            }
+            rest = &rest[end..];
+        }
+
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Pages of the site that are reached through a recipient's own token.
+const RECIPIENT_PAGES: &[&str] = &["newsletter/unsubscribe", "newsletter/preferences"];
+
+/// Origins whose links can carry per-recipient tokens: the site itself and,
+/// when configured, the public origin of this service (tracking redirects).
+pub fn own_origins() -> Vec<String> {
+    let mut origins = vec![crate::services::newsletter::base_url()];
+    if let Some(api) = std::env::var("API_PUBLIC_URL").ok().filter(|s| !s.is_empty()) {
+        origins.push(api);
+    }
+    origins
+}
+
+/// The path of `url` if it points at one of `origins`, without query or fragment.
+fn own_path<'a>(url: &'a str, origins: &[String]) -> Option<&'a str> {
+    origins
+        .iter()
+        .find_map(|origin| url.strip_prefix(origin.trim_end_matches('/')).filter(|rest| rest.starts_with('/')))
+        .and_then(|rest| rest.split(['?', '#']).next())
+}
+
+/// Removes per-recipient parts from a sent issue so it can be published:
+/// click redirects are unwrapped to their target, the open pixel is dropped
+/// and links to the unsubscribe and preferences pages become `#`. Only links
+/// to `origins` are touched; links to other sites are kept as written.
+pub fn strip_recipient_markup(html: &str, origins: &[String]) -> String {
+    const HREF: &str = "href=\"";
+
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(HREF) {
+        let value_start = start + HREF.len();
+        out.push_str(&rest[..value_start]);
+        rest = &rest[value_start..];
+
+        let Some(end) = rest.find('"') else {
+            break;
+        };
+        let href = &rest[..end];
+        let url = href.replace("&amp;", "&");
+        let path = own_path(&url, origins);
+
+        if path == Some("/api/newsletter/t/click") {
+            let target = url
+                .split_once('?')
+                .and_then(|(_, query)| {
+                    query
+                        .split('&')
+                        .find_map(|pair| pair.strip_prefix("u="))
+                        .and_then(|u| urlencoding::decode(u).ok())
+                        .map(|u| u.into_owned())
+                })
+                .unwrap_or_else(|| "#".to_string());
+            out.push_str(&target.replace('&', "&amp;").replace('"', "&quot;"));
+        } else if path.is_some_and(|path| {
+            RECIPIENT_PAGES
+                .iter()
+                .any(|page| path.trim_end_matches('/').ends_with(&format!("/{}", page)))
+        }) {
+            out.push('#');
+        } else {
+            out.push_str(href);
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+
+    // Drop any <img> pointing at the open pixel.
+    let mut cleaned = String::with_capacity(out.len());
+    let mut rest = out.as_str();
+    while let Some(start) = rest.find("<img") {
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start..start + len + 1];
+        cleaned.push_str(&rest[..start]);
+        let pixel = origins
+            .iter()
+            .any(|origin| tag.contains(&format!("{}/api/newsletter/t/open", origin.trim_end_matches('/'))));
+        if !pixel {
+            cleaned.push_str(tag);
+        }
+        rest = &rest[start + len + 1..];
+    }
+    cleaned.push_str(rest);
+    cleaned
+}
+
+/// `DNT: 1` or `Sec-GPC: 1` on the tracking request means we serve the
+/// response without recording anything.
+pub fn do_not_track(headers: &axum::http::HeaderMap) -> bool {
+    ["dnt", "sec-gpc"].iter().any(|name| {
+        headers
+            .get(*name)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.trim() == "1")
+    })
+}
+
+/// Records an open or click for a delivery that was sent with tracking.
+/// Returns `false` when the delivery is unknown or untracked, or the
+/// subscriber has opted out of tracking since it was sent.
+pub fn record_event(
+    conn: &mut PgConnection,
+    delivery_id: &str,
+    kind: &str,
+    url: Option<&str>,
+) -> QueryResult<bool> {
+    let issue_id: Option<String> = newsletter_deliveries::table
+        .inner_join(newsletter_subscriptions::table)
+        .filter(newsletter_deliveries::id.eq(delivery_id))
+        .filter(newsletter_deliveries::tracked.eq(true))
+        .filter(newsletter_subscriptions::tracking_opt_out.eq(false))
+        .select(newsletter_deliveries::issue_id)
+        .first(conn)
+        .optional()?;
+
+    let Some(issue_id) = issue_id else {
+        return Ok(false);
+    };
+
+    diesel::insert_into(newsletter_events::table)
+        .values((
+            newsletter_events::id.eq(cuid2::create_id()),
+            newsletter_events::delivery_id.eq(delivery_id),
+            newsletter_events::issue_id.eq(issue_id),
+            newsletter_events::type_.eq(kind),
+            newsletter_events::url.eq(url),
+        ))
+        .execute(conn)?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origins() -> Vec<String> {
+        vec!["https://blog.example.com".to_string(), "https://api.example.com/".to_string()]
+    }
+
+    #[test]
+    fn own_recipient_links_are_stripped() {
+        let html = concat!(
+            "<a href=\"https://api.example.com/api/newsletter/t/click?d=d1&amp;u=https%3A%2F%2Fexample.org%2F%3Fa%3D1%26b%3D2&amp;s=ab\">Read</a>",
+            "<a href=\"https://blog.example.com/ko/newsletter/unsubscribe?token=t1\">Unsubscribe</a>",
+            "<a href=\"https://blog.example.com/en/newsletter/preferences?token=t2\">Preferences</a>",
+            "<img src=\"https://api.example.com/api/newsletter/t/open?d=d1&amp;s=ab\" width=\"1\">",
+        );
+
+        assert_eq!(
+            strip_recipient_markup(html, &origins()),
+            concat!(
+                "<a href=\"https://example.org/?a=1&amp;b=2\">Read</a>",
+                "<a href=\"#\">Unsubscribe</a>",
+                "<a href=\"#\">Preferences</a>",
+            )
+        );
+    }
+
+    #[test]
+    fn other_links_with_tokens_are_kept() {
+        let html = concat!(
+            "<a href=\"https://github.com/login/oauth?token=abc\">GitHub</a>",
+            "<a href=\"https://blog.example.com/ko/posts/reset-token?token=demo\">Post</a>",
+            "<a href=\"https://other.example/ko/newsletter/unsubscribe?token=x\">Elsewhere</a>",
+            "<a href=\"https://blog.example.com.evil.test/ko/newsletter/unsubscribe?token=x\">Lookalike</a>",
+            "<img src=\"https://other.example/api/newsletter/t/open?d=1\">",
+        );
+
+        assert_eq!(strip_recipient_markup(html, &origins()), html);
+    }
+}
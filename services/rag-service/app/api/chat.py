@@ -1,32 +1,68 @@
 """Chat API endpoint"""
-from fastapi import APIRouter, HTTPException
+from fastapi import APIRouter, Header, HTTPException
 from fastapi.responses import StreamingResponse
-from app.models.schemas import ChatRequest, SourceDocument
+from app.models.schemas import ChatRequest, HistoryTurn, SourceDocument
 from app.services.gemini import GeminiService
 from app.services.vector_store import VectorStore
+from app.services.answer_streams import AnswerStreams, format_events
 import json
+from typing import List, Optional
 
 router = APIRouter()
 
 # Initialize services
 gemini_service = GeminiService()
 vector_store = VectorStore()
+answer_streams = AnswerStreams()
+
+SSE_HEADERS = {
+    "Cache-Control": "no-cache",
+    "Connection": "keep-alive",
+    "X-Accel-Buffering": "no"
+}
+
+
+# How many earlier questions are folded into the retrieval query
+RETRIEVAL_HISTORY_QUESTIONS = 2
+
+
+def retrieval_query(query: str, history: List[HistoryTurn]) -> str:
+    """The text embedded for the vector search: the question, preceded by the
+    reader's latest earlier questions"""
+    earlier = [turn.content for turn in history if turn.role == "user"]
+    return "\n".join(earlier[-RETRIEVAL_HISTORY_QUESTIONS:] + [query])
 
 
 @router.post("/chat")
-async def chat(request: ChatRequest):
+async def chat(request: ChatRequest, last_event_id: Optional[str] = Header(default=None)):
     """
     Chat endpoint with streaming response
 
     Args:
-        request: ChatRequest with query and locale
+        request: ChatRequest with query, locale and earlier turns
+        last_event_id: `id` of the last event a reconnecting client received
 
     Returns:
-        StreamingResponse with chat completion
+        StreamingResponse with chat completion. Every event has an `id`; a
+        request with `Last-Event-ID` continues that answer after it
     """
+    if last_event_id:
+        resumed = answer_streams.resume(last_event_id)
+        if resumed is None:
+            raise HTTPException(status_code=404, detail="This answer can no longer be resumed")
+        stream, after = resumed
+        return StreamingResponse(
+            format_events(stream, after),
+            media_type="text/event-stream",
+            headers=SSE_HEADERS
+        )
+
     try:
-        # 1. Generate query embedding
-        query_embedding = await gemini_service.embed_query(request.query)
+        # 1. Generate query embedding (follow-ups like "what about the second
+        # one?" only make sense together with the earlier questions)
+        query_embedding = await gemini_service.embed_query(
+            retrieval_query(request.query, request.history)
+        )
 
         # 2. Vector similarity search
         relevant_docs = vector_store.search(
@@ -68,39 +104,39 @@ async def chat(request: ChatRequest):
 
         context = "\n\n---\n\n".join(context_parts)
 
-        # 4. Stream response
+        # 4. Stream response. The answer is generated in the background so
+        # that a client that drops the connection can resume it.
         async def generate():
-            """Generator for streaming response"""
+            """Generator for the `data` of each event"""
             # First, send sources as metadata
             metadata = {
                 "type": "sources",
                 "sources": [source.model_dump() for source in sources]
             }
-            yield f"data: {json.dumps(metadata, ensure_ascii=False)}\n\n"
+            yield json.dumps(metadata, ensure_ascii=False)
 
             # Then stream the answer
             async for chunk in gemini_service.chat_stream(
                 query=request.query,
                 context=context,
-                locale=request.locale
+                locale=request.locale,
+                history=request.history,
+                flags=request.flags
             ):
                 data = {
                     "type": "content",
                     "content": chunk
                 }
-                yield f"data: {json.dumps(data, ensure_ascii=False)}\n\n"
+                yield json.dumps(data, ensure_ascii=False)
 
             # Send done signal
-            yield "data: {\"type\": \"done\"}\n\n"
+            yield "{\"type\": \"done\"}"
 
+        stream = answer_streams.start(generate())
         return StreamingResponse(
-            generate(),
+            format_events(stream),
             media_type="text/event-stream",
-            headers={
-                "Cache-Control": "no-cache",
-                "Connection": "keep-alive",
-                "X-Accel-Buffering": "no"
-            }
+            headers=SSE_HEADERS
         )
 
     except HTTPException:
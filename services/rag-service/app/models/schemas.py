@@ -3,10 +3,18 @@ from pydantic import BaseModel, Field
 from typing import List, Optional
 
 
+class HistoryTurn(BaseModel):
+    """An earlier turn of the conversation"""
+    role: str = Field(..., pattern="^(user|assistant)$", description="Who said it")
+    content: str = Field(..., description="What was said")
+
+
 class ChatRequest(BaseModel):
     """Chat request model"""
     query: str = Field(..., min_length=1, max_length=1000, description="User query")
     locale: str = Field(default="ko", pattern="^(ko|en)$", description="Language locale")
+    history: List[HistoryTurn] = Field(default_factory=list, description="Earlier turns, oldest first")
+    flags: List[str] = Field(default_factory=list, description="Screening rules the question tripped, e.g. prompt_injection")
 
     model_config = {
         "json_schema_extra": {
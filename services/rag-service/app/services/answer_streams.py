@@ -0,0 +1,100 @@
+"""Buffered answer streams that a reconnecting client can resume"""
+import asyncio
+import os
+import time
+import uuid
+from typing import AsyncGenerator, AsyncIterator, Dict, List, Optional, Tuple
+
+# How long a finished (or abandoned) answer stays available for resuming
+STREAM_TTL_SECONDS = int(os.getenv("STREAM_TTL_SECONDS", "300"))
+
+
+class AnswerStream:
+    """SSE `data` payloads of one answer, numbered from 1"""
+
+    def __init__(self):
+        self.id = uuid.uuid4().hex
+        self.events: List[str] = []
+        self.done = False
+        self.touched = time.monotonic()
+        self._changed = asyncio.Condition()
+
+    async def push(self, data: str):
+        async with self._changed:
+            self.events.append(data)
+            self.touched = time.monotonic()
+            self._changed.notify_all()
+
+    async def finish(self):
+        async with self._changed:
+            self.done = True
+            self.touched = time.monotonic()
+            self._changed.notify_all()
+
+    async def follow(self, after: int = 0) -> AsyncGenerator[Tuple[int, str], None]:
+        """Yield (sequence, data) for every event after `after`, waiting for
+        new ones until the answer is done"""
+        seen = after
+        while True:
+            async with self._changed:
+                await self._changed.wait_for(lambda: len(self.events) > seen or self.done)
+                batch = self.events[seen:]
+                done = self.done
+            for data in batch:
+                seen += 1
+                yield seen, data
+            if done and seen >= len(self.events):
+                return
+
+
+class AnswerStreams:
+    """Answers generated in the background, kept per process for resuming"""
+
+    def __init__(self):
+        self._streams: Dict[str, AnswerStream] = {}
+        # Background tasks are referenced here so they are not collected
+        self._tasks = set()
+
+    def _expire(self):
+        cutoff = time.monotonic() - STREAM_TTL_SECONDS
+        for stream_id in [sid for sid, s in self._streams.items() if s.touched < cutoff]:
+            del self._streams[stream_id]
+
+    def start(self, events: AsyncIterator[str]) -> AnswerStream:
+        """Run `events` to completion in the background, buffering each one"""
+        self._expire()
+        stream = AnswerStream()
+        self._streams[stream.id] = stream
+
+        async def produce():
+            try:
+                async for data in events:
+                    await stream.push(data)
+            except Exception as e:
+                print(f"Error while generating answer stream {stream.id}: {e}")
+            finally:
+                await stream.finish()
+
+        task = asyncio.create_task(produce())
+        self._tasks.add(task)
+        task.add_done_callback(self._tasks.discard)
+        return stream
+
+    def resume(self, last_event_id: str) -> Optional[Tuple[AnswerStream, int]]:
+        """The stream and sequence a `Last-Event-ID` of `<stream>:<seq>` points at"""
+        self._expire()
+        stream_id, _, seq = last_event_id.rpartition(":")
+        stream = self._streams.get(stream_id)
+        if stream is None or not seq.isdigit():
+            return None
+        return stream, int(seq)
+
+
+def format_events(stream: AnswerStream, after: int = 0) -> AsyncGenerator[str, None]:
+    """SSE text for a stream, each event carrying `id: <stream>:<seq>`"""
+
+    async def render():
+        async for seq, data in stream.follow(after):
+            yield f"id: {stream.id}:{seq}\ndata: {data}\n\n"
+
+    return render()
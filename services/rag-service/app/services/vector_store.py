@@ -73,6 +73,12 @@ class VectorStore:
         finally:
             conn.close()
 
+    def _invalidate_answer_cache(self, cur):
+        """Drop answers the blog API cached from the old index (if the table exists)"""
+        cur.execute("""SELECT to_regclass('"ChatAnswerCache"')""")
+        if cur.fetchone()[0] is not None:
+            cur.execute('DELETE FROM "ChatAnswerCache"')
+
     def upsert_embeddings(self, documents: List[Dict]):
         """
         Upsert embeddings into PostgreSQL
@@ -112,6 +118,8 @@ class VectorStore:
                         locale = EXCLUDED.locale,
                         "contentType" = EXCLUDED."contentType"
                 """, data)
+
+                self._invalidate_answer_cache(cur)
                 
             conn.commit()
             print(f"✅ Upserted {len(documents)} embeddings to Supabase")
@@ -231,6 +239,7 @@ class VectorStore:
         try:
             with conn.cursor() as cur:
                 cur.execute('DELETE FROM "Embedding"')
+                self._invalidate_answer_cache(cur)
             conn.commit()
             print("✅ Cleared all database embeddings")
         finally: